@@ -0,0 +1,121 @@
+//! Administration of accounts
+//!
+//! # Profile fields
+//!
+//! The account model is provided by the application through [`AuthModels`],
+//! so it may contain any additional fields.
+//! Since the handlers create accounts using [`AuthModels::insertable_account`],
+//! these fields need default values set by the application's insertable.
+//!
+//! Data which can't be defaulted is better stored in a separate model pointing to the account.
+//! Set an [`AccountHook`] using [`AuthModule::set_account_hook`] to insert it
+//! whenever an account is created.
+
+use crate::utils::unix_now;
+use crate::{AuthModels, AuthModule};
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::Module;
+use rorm::crud::query::QueryBuilder;
+use rorm::crud::update::UpdateBuilder;
+use rorm::db::Transaction;
+use rorm::internal::field::Field;
+use rorm::{FieldAccess, Model};
+use std::future::Future;
+use std::pin::Pin;
+
+/// The future returned by [`AccountHook::created`]
+pub type AccountHookFuture<'a> = Pin<Box<dyn Future<Output = ApiResult<()>> + Send + 'a>>;
+
+/// Hook for applications storing additional data about accounts
+pub trait AccountHook<M: AuthModels>: Send + Sync + 'static {
+    /// Called in the same transaction right after an account has been created
+    ///
+    /// This happens on registration, on the first login through the OpenID Connect provider
+    /// and when an admin creates an account.
+    /// Returning an error aborts the account's creation.
+    fn created<'a>(
+        &'a self,
+        tx: &'a mut Transaction,
+        account_pk: &'a <<M::Account as Model>::Primary as Field>::Type,
+    ) -> AccountHookFuture<'a>;
+}
+
+impl<M: AuthModels> AuthModule<M> {
+    /// Sets the hook called whenever an account is created
+    ///
+    /// This can only be set once, returns the rejected hook if it has already been set.
+    pub fn set_account_hook(
+        &self,
+        hook: impl AccountHook<M>,
+    ) -> Result<(), Box<dyn AccountHook<M>>> {
+        self.account_hook.set(Box::new(hook))
+    }
+
+    /// Disables an account, preventing it from logging in
+    ///
+    /// The account is logged out of all its sessions and its refresh tokens are revoked.
+    /// Unlike deleting the account, this keeps all its data.
+    pub async fn disable_account(&self, identifier: &str) -> ApiResult<()> {
+        let mut tx = self.db.start_transaction().await?;
+
+        let (account_pk,) = QueryBuilder::new(&mut tx, (M::account_pk(),))
+            .condition(M::account_id().equals(identifier))
+            .optional()
+            .await?
+            .ok_or_else(|| ApiError::not_found("Account not found"))?;
+
+        UpdateBuilder::new(&mut tx)
+            .condition(M::account_id().equals(identifier))
+            .set(M::account_disabled_at(), Some(unix_now()))
+            .exec()
+            .await?;
+
+        rorm::delete!(&mut tx, M::RefreshToken)
+            .condition(M::refresh_token_fm().equals(&account_pk))
+            .await?;
+
+        tx.commit().await?;
+
+        self.revoke_sessions(&account_pk).await?;
+        Ok(())
+    }
+
+    /// Enables a disabled account again
+    pub async fn enable_account(&self, identifier: &str) -> ApiResult<()> {
+        let updated = UpdateBuilder::new(&self.db)
+            .condition(M::account_id().equals(identifier))
+            .set(M::account_disabled_at(), None)
+            .exec()
+            .await?;
+        if updated == 0 {
+            return Err(ApiError::not_found("Account not found").into());
+        }
+        Ok(())
+    }
+
+    /// Checks whether an account has been disabled
+    ///
+    /// The login handlers check `account_disabled_at` while querying the account by its identifier.
+    /// This is for flows which only know the account's primary key.
+    pub async fn is_account_disabled(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    ) -> Result<bool, rorm::Error> {
+        let disabled = QueryBuilder::new(&self.db, (M::account_pk(),))
+            .condition(M::account_disabled_at().is_some())
+            .all()
+            .await?;
+        Ok(disabled.iter().any(|(pk,)| pk == account_pk))
+    }
+}
+
+/// Calls the [`AccountHook`] after an account has been created, if one has been set
+pub(crate) async fn created<M: AuthModels>(
+    tx: &mut Transaction,
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+) -> ApiResult<()> {
+    if let Some(hook) = AuthModule::<M>::global().account_hook.get() {
+        hook.created(tx, account_pk).await?;
+    }
+    Ok(())
+}
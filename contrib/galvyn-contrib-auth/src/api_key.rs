@@ -0,0 +1,103 @@
+//! Authentication of machine clients using api keys
+//!
+//! Api keys are created by logged-in users through the handlers included by
+//! [`AuthRouterBuilder::with_api_keys`](crate::AuthRouterBuilder::with_api_keys).
+//! Clients send them in the `Authorization` header (`Authorization: Bearer <key>`),
+//! which is read by the [`ApiKeyAuth`] extractor independently of cookie sessions.
+
+use crate::utils::{hash_token, unix_now};
+use crate::{AuthModels, AuthModule};
+use galvyn_core::handler::request_part::{RequestPart, ShouldBeRequestPart};
+use galvyn_core::re_exports::axum::extract::FromRequestParts;
+use galvyn_core::re_exports::axum::http::header;
+use galvyn_core::re_exports::axum::http::request::Parts;
+use galvyn_core::stuff::api_error::ApiError;
+use galvyn_core::Module;
+use rorm::crud::query::QueryBuilder;
+use rorm::internal::field::Field;
+use rorm::prelude::ForeignModelByField;
+use rorm::{FieldAccess, Model};
+
+/// Extractor authenticating a request using an api key
+///
+/// Requests without a valid and unexpired key are rejected with `401 Unauthorized`,
+/// requests whose account has been disabled with `403 Forbidden`.
+pub struct ApiKeyAuth<M: AuthModels> {
+    /// The primary key of the account the api key belongs to
+    pub account_pk: <<M::Account as Model>::Primary as Field>::Type,
+
+    /// The scopes the api key is restricted to
+    pub scopes: Vec<String>,
+}
+
+impl<M: AuthModels> ApiKeyAuth<M> {
+    /// Checks whether the api key has been granted a scope
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|granted| granted == scope)
+    }
+
+    /// Rejects the request with `403 Forbidden` unless the api key has been granted a scope
+    #[track_caller]
+    pub fn require_scope(&self, scope: &str) -> Result<(), ApiError> {
+        if self.has_scope(scope) {
+            Ok(())
+        } else {
+            Err(ApiError::forbidden(format!("Missing scope: {scope}")))
+        }
+    }
+}
+
+impl<S, M> FromRequestParts<S> for ApiKeyAuth<M>
+where
+    S: Send + Sync,
+    M: AuthModels,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let key = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::unauthorized("Missing api key"))?;
+
+        let (account, scopes, expires_at) = QueryBuilder::new(
+            &AuthModule::<M>::global().db,
+            (
+                M::api_key_fm(),
+                M::api_key_scopes(),
+                M::api_key_expires_at(),
+            ),
+        )
+        .condition(M::api_key_secret_hash().equals(&hash_token(key.trim())))
+        .optional()
+        .await
+        .map_err(ApiError::server_error)?
+        .ok_or_else(|| ApiError::unauthorized("Invalid api key"))?;
+
+        if expires_at.is_some_and(|expires_at| expires_at <= unix_now()) {
+            return Err(ApiError::unauthorized("Expired api key"));
+        }
+
+        let account_pk = match account {
+            ForeignModelByField::Key(x) => x,
+            ForeignModelByField::Instance(_) => unreachable!(),
+        };
+
+        let disabled = AuthModule::<M>::global()
+            .is_account_disabled(&account_pk)
+            .await
+            .map_err(ApiError::server_error)?;
+        if disabled {
+            return Err(ApiError::forbidden("Account has been disabled"));
+        }
+        Ok(Self {
+            account_pk,
+            scopes: scopes.0,
+        })
+    }
+}
+
+impl<M: AuthModels> ShouldBeRequestPart for ApiKeyAuth<M> {}
+impl<M: AuthModels> RequestPart for ApiKeyAuth<M> {}
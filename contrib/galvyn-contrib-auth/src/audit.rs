@@ -0,0 +1,176 @@
+//! Audit log of authentication events
+//!
+//! Logins, logouts, password changes and key enrollments are stored as `AuthEvent`s
+//! together with the client's address and user agent.
+//! They can be queried using [`AuthModule::get_auth_events`].
+//!
+//! Failed logins are only recorded for existing accounts,
+//! attempts using unknown identifiers are merely counted by the rate limit.
+
+use crate::utils::unix_now;
+use crate::{AuthModels, AuthModule};
+use galvyn_core::handler::request_part::{RequestPart, ShouldBeRequestPart};
+use galvyn_core::re_exports::axum::extract::FromRequestParts;
+use galvyn_core::re_exports::axum::http::header;
+use galvyn_core::re_exports::axum::http::request::Parts;
+use galvyn_core::stuff::api_error::ApiError;
+use galvyn_core::stuff::client_ip::ClientIp;
+use galvyn_core::Module;
+use rorm::crud::query::QueryBuilder;
+use rorm::db::Transaction;
+use rorm::internal::field::Field;
+use rorm::{insert, DbEnum, FieldAccess, Model};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// The maximum number of characters of a user agent which are stored
+const MAX_USER_AGENT_LENGTH: usize = 255;
+
+/// The kinds of events recorded in the audit log
+#[derive(DbEnum, Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub enum AuthEventKind {
+    /// An account logged in
+    LoginSucceeded,
+    /// Someone failed to login into an account
+    LoginFailed,
+    /// An account logged out
+    Logout,
+    /// An account's password has been changed or reset
+    PasswordChanged,
+    /// A second factor (i.e. a TOTP key or a passkey) has been added to an account
+    KeyEnrolled,
+    /// An admin started impersonating an account
+    ImpersonationStarted,
+    /// An admin stopped impersonating an account
+    ImpersonationEnded,
+}
+
+/// A recorded event returned by [`AuthModule::get_auth_events`]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AuthEventInfo {
+    pub kind: AuthEventKind,
+    /// The address of the client which caused the event
+    pub ip: Option<String>,
+    /// The user agent of the client which caused the event
+    pub user_agent: Option<String>,
+    /// The identifier of the account which caused the event on behalf of this one
+    /// (i.e. the admin impersonating it)
+    pub actor: Option<String>,
+    /// The point in time (in seconds since the unix epoch) the event occurred
+    pub created_at: i64,
+}
+
+/// Extractor for the details about the client which are stored with an event
+///
+/// Unlike [`ClientIp`], this doesn't reject requests if the server has been started without `ConnectInfo`.
+#[derive(Clone, Debug, Default)]
+pub struct AuditContext {
+    /// The address of the client
+    pub ip: Option<String>,
+    /// The client's `User-Agent` header
+    pub user_agent: Option<String>,
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let ip = ClientIp::from_request_parts(parts, state)
+            .await
+            .ok()
+            .map(|ClientIp(ip)| ip.to_string());
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(MAX_USER_AGENT_LENGTH).collect());
+        Ok(Self { ip, user_agent })
+    }
+}
+
+impl ShouldBeRequestPart for AuditContext {}
+impl RequestPart for AuditContext {}
+
+impl<M: AuthModels> AuthModule<M> {
+    /// Gets an account's recorded events, newest first
+    pub async fn get_auth_events(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+        limit: u64,
+    ) -> Result<Vec<AuthEventInfo>, rorm::Error> {
+        let events = QueryBuilder::new(
+            &self.db,
+            (
+                M::auth_event_kind(),
+                M::auth_event_ip(),
+                M::auth_event_user_agent(),
+                M::auth_event_actor(),
+                M::auth_event_created_at(),
+            ),
+        )
+        .condition(M::auth_event_fm().equals(account_pk))
+        .order_desc(M::auth_event_created_at())
+        .limit(limit)
+        .all()
+        .await?;
+        Ok(events
+            .into_iter()
+            .map(|(kind, ip, user_agent, actor, created_at)| AuthEventInfo {
+                kind,
+                ip,
+                user_agent,
+                actor,
+                created_at,
+            })
+            .collect())
+    }
+}
+
+/// Records an event for an account
+pub(crate) async fn record<M: AuthModels>(
+    tx: &mut Transaction,
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    kind: AuthEventKind,
+    context: &AuditContext,
+) -> Result<(), rorm::Error> {
+    record_by::<M>(tx, account_pk, None, kind, context).await
+}
+
+/// Records an event for an account which another account caused on its behalf
+///
+/// `actor` is the identifier of the other account (i.e. the admin impersonating `account_pk`).
+pub(crate) async fn record_by<M: AuthModels>(
+    tx: &mut Transaction,
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    actor: Option<String>,
+    kind: AuthEventKind,
+    context: &AuditContext,
+) -> Result<(), rorm::Error> {
+    insert!(tx, M::AuthEvent)
+        .return_nothing()
+        .single(&M::insertable_auth_event(
+            kind,
+            context.ip.clone(),
+            context.user_agent.clone(),
+            actor,
+            unix_now(),
+            account_pk,
+        ))
+        .await
+}
+
+/// Records a failed login for the account with the given identifier, if it exists
+pub(crate) async fn record_failed_login<M: AuthModels>(
+    tx: &mut Transaction,
+    identifier: &str,
+    context: &AuditContext,
+) -> Result<(), rorm::Error> {
+    let account = QueryBuilder::new(&mut *tx, (M::account_pk(),))
+        .condition(M::account_id().equals(identifier))
+        .optional()
+        .await?;
+    if let Some((account_pk,)) = account {
+        record::<M>(tx, &account_pk, AuthEventKind::LoginFailed, context).await?;
+    }
+    Ok(())
+}
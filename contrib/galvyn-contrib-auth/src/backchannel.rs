@@ -0,0 +1,115 @@
+//! Verification of the logout tokens sent by the OpenID Connect provider
+//!
+//! See [OpenID Connect Back-Channel Logout](https://openid.net/specs/openid-connect-backchannel-1_0.html).
+//!
+//! The provider's keys are fetched once while the module is initialized.
+
+use galvyn_core::stuff::api_error::ApiError;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{DecodingKey, Validation};
+use openidconnect::core::{CoreIdToken, CoreProviderMetadata};
+use openidconnect::ClientId;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The event a logout token has to contain
+const BACKCHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+/// Verifies logout tokens using the provider's keys
+pub(crate) struct LogoutTokenVerifier {
+    issuer: String,
+    client_id: String,
+    jwks: JwkSet,
+}
+
+/// The claims identifying the sessions to log out
+///
+/// At least one of them is present.
+pub(crate) struct LogoutClaims {
+    pub(crate) sub: Option<String>,
+    pub(crate) sid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawLogoutClaims {
+    sub: Option<String>,
+    sid: Option<String>,
+    #[serde(default)]
+    events: HashMap<String, serde_json::Value>,
+    nonce: Option<serde_json::Value>,
+}
+
+impl LogoutTokenVerifier {
+    /// Constructs the verifier from the provider's metadata
+    pub(crate) fn new(
+        metadata: &CoreProviderMetadata,
+        client_id: &ClientId,
+    ) -> Result<Self, serde_json::Error> {
+        // The provider's keys are converted into the format understood by `jsonwebtoken`
+        let jwks = serde_json::from_value(serde_json::to_value(metadata.jwks())?)?;
+        Ok(Self {
+            issuer: metadata.issuer().as_str().to_string(),
+            client_id: client_id.as_str().to_string(),
+            jwks,
+        })
+    }
+
+    /// Verifies a logout token and returns the sessions it logs out
+    pub(crate) fn verify(&self, token: &str) -> Result<LogoutClaims, ApiError> {
+        let invalid = |_| ApiError::client_error("Invalid logout token");
+
+        let header = jsonwebtoken::decode_header(token).map_err(invalid)?;
+        let jwk = match &header.kid {
+            Some(kid) => self.jwks.find(kid),
+            None => self.jwks.keys.first(),
+        }
+        .ok_or_else(|| ApiError::client_error("Unknown key"))?;
+        let key = DecodingKey::from_jwk(jwk).map_err(invalid)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_audience(&[&self.client_id]);
+        validation.set_required_spec_claims(&["iss", "aud", "iat", "exp"]);
+        let claims = jsonwebtoken::decode::<RawLogoutClaims>(token, &key, &validation)
+            .map_err(invalid)?
+            .claims;
+
+        // Logout tokens must not be mistaken for ID tokens and vice versa
+        if !claims.events.contains_key(BACKCHANNEL_LOGOUT_EVENT) || claims.nonce.is_some() {
+            return Err(ApiError::client_error("Not a logout token"));
+        }
+        if claims.sub.is_none() && claims.sid.is_none() {
+            return Err(ApiError::client_error(
+                "Logout token has neither sub nor sid",
+            ));
+        }
+        Ok(LogoutClaims {
+            sub: claims.sub,
+            sid: claims.sid,
+        })
+    }
+}
+
+/// Reads the `sid` claim from an ID token which has already been verified
+///
+/// `openidconnect`'s core claims don't include it.
+pub(crate) fn session_id_claim(id_token: &CoreIdToken) -> Option<String> {
+    #[derive(Deserialize)]
+    struct SidClaim {
+        sid: Option<String>,
+    }
+
+    let mut validation = Validation::default();
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation.validate_aud = false;
+    validation.required_spec_claims.clear();
+    jsonwebtoken::decode::<SidClaim>(
+        &id_token.to_string(),
+        &DecodingKey::from_secret(&[]),
+        &validation,
+    )
+    .ok()?
+    .claims
+    .sid
+}
@@ -0,0 +1,148 @@
+//! Captchas protecting the registration and logins against bots
+//!
+//! Once a [`CaptchaVerifier`] has been set using [`AuthModule::set_captcha_verifier`],
+//! registrations always require a solved captcha
+//! and logins require one after `CAPTCHA_AFTER_FAILURES` failed logins
+//! from the same client ip or for the same identifier (see [`AuthConfig`](crate::module::AuthConfig)).
+//! The clients send the token produced by the captcha's widget in the request's `captcha` field.
+//!
+//! Enable the `captcha` feature for [`SiteVerify`] which implements hCaptcha and Cloudflare Turnstile.
+
+use crate::throttle::{self, ThrottleKeys};
+use crate::{AuthModels, AuthModule};
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::stuff::client_ip::ClientIp;
+use galvyn_core::Module;
+use rorm::db::Transaction;
+use std::future::Future;
+use std::net::IpAddr;
+use std::pin::Pin;
+
+/// The future returned by [`CaptchaVerifier::verify`]
+pub type CaptchaFuture<'a> = Pin<Box<dyn Future<Output = ApiResult<bool>> + Send + 'a>>;
+
+/// Verifies the tokens of solved captchas with the captcha's provider
+pub trait CaptchaVerifier: Send + Sync + 'static {
+    /// Checks whether a token belongs to a captcha solved by the client
+    ///
+    /// Returns `Ok(false)` for invalid tokens and `Err` if the provider couldn't be reached.
+    fn verify<'a>(&'a self, token: &'a str, client_ip: IpAddr) -> CaptchaFuture<'a>;
+}
+
+impl<M: AuthModels> AuthModule<M> {
+    /// Sets the verifier requiring captchas
+    ///
+    /// Without it, no captchas are required.
+    /// This can only be set once, returns the rejected verifier if it has already been set.
+    pub fn set_captcha_verifier(
+        &self,
+        verifier: impl CaptchaVerifier,
+    ) -> Result<(), Box<dyn CaptchaVerifier>> {
+        self.captcha_verifier.set(Box::new(verifier))
+    }
+}
+
+/// Rejects the request unless it contains a solved captcha
+///
+/// Does nothing if no [`CaptchaVerifier`] has been set.
+pub(crate) async fn require<M: AuthModels>(
+    token: Option<&str>,
+    ClientIp(client_ip): ClientIp,
+) -> ApiResult<()> {
+    let Some(verifier) = AuthModule::<M>::global().captcha_verifier.get() else {
+        return Ok(());
+    };
+
+    let token = token.ok_or_else(|| ApiError::client_error("Missing captcha"))?;
+    if !verifier.verify(token, client_ip).await? {
+        return Err(ApiError::client_error("Invalid captcha").into());
+    }
+    Ok(())
+}
+
+/// Rejects the login unless it contains a solved captcha, if there have been too many failed logins
+pub(crate) async fn require_after_failures<M: AuthModels>(
+    tx: &mut Transaction,
+    keys: &ThrottleKeys,
+    token: Option<&str>,
+    client_ip: ClientIp,
+) -> ApiResult<()> {
+    let module = AuthModule::<M>::global();
+    if module.captcha_verifier.get().is_none() {
+        return Ok(());
+    }
+
+    if throttle::failures::<M>(tx, keys).await? >= module.local.captcha_after_failures {
+        require::<M>(token, client_ip).await?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "captcha")]
+pub use self::site_verify::SiteVerify;
+
+#[cfg(feature = "captcha")]
+mod site_verify {
+    use super::{CaptchaFuture, CaptchaVerifier};
+    use serde::Deserialize;
+    use std::net::IpAddr;
+
+    /// [`CaptchaVerifier`] for providers implementing the `siteverify` api
+    ///
+    /// Both hCaptcha and Cloudflare Turnstile provide this api.
+    pub struct SiteVerify {
+        url: &'static str,
+        secret: String,
+        client: reqwest::Client,
+    }
+
+    impl SiteVerify {
+        /// Verifies hCaptcha tokens using your account's secret key
+        pub fn hcaptcha(secret: String) -> Self {
+            Self::new("https://api.hcaptcha.com/siteverify", secret)
+        }
+
+        /// Verifies Cloudflare Turnstile tokens using your widget's secret key
+        pub fn turnstile(secret: String) -> Self {
+            Self::new(
+                "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+                secret,
+            )
+        }
+
+        fn new(url: &'static str, secret: String) -> Self {
+            Self {
+                url,
+                secret,
+                client: reqwest::Client::new(),
+            }
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct SiteVerifyResponse {
+        success: bool,
+    }
+
+    impl CaptchaVerifier for SiteVerify {
+        fn verify<'a>(&'a self, token: &'a str, client_ip: IpAddr) -> CaptchaFuture<'a> {
+            Box::pin(async move {
+                let remote_ip = client_ip.to_string();
+                let response: SiteVerifyResponse = self
+                    .client
+                    .post(self.url)
+                    .form(&[
+                        ("secret", self.secret.as_str()),
+                        ("response", token),
+                        ("remoteip", remote_ip.as_str()),
+                    ])
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                Ok(response.success)
+            })
+        }
+    }
+}
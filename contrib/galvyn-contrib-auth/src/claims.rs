@@ -0,0 +1,60 @@
+//! Mapping of the OpenID Connect provider's claims to accounts
+//!
+//! By default, accounts logging in through the provider for the first time
+//! are created with their `preferred_username` as identifier and the claims are ignored otherwise.
+//! Set a [`ClaimsMapper`] using [`AuthModule::set_claims_mapper`] to choose the identifier
+//! and to copy claims (for example the display name or email) into your account model.
+
+use crate::{AuthModels, AuthModule};
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use openidconnect::core::CoreIdTokenClaims;
+use rorm::db::Transaction;
+use rorm::internal::field::Field;
+use rorm::Model;
+use std::future::Future;
+use std::pin::Pin;
+
+/// The future returned by [`ClaimsMapper::sync`]
+pub type ClaimsFuture<'a> = Pin<Box<dyn Future<Output = ApiResult<()>> + Send + 'a>>;
+
+/// Maps the claims of an ID token to an account
+pub trait ClaimsMapper<M: AuthModels>: Send + Sync + 'static {
+    /// Chooses the identifier of an account created on its first login
+    ///
+    /// Defaults to the `preferred_username` claim.
+    fn identifier(&self, claims: &CoreIdTokenClaims) -> ApiResult<String> {
+        default_identifier(claims)
+    }
+
+    /// Updates an account from the claims
+    ///
+    /// It is called in the same transaction right after the account has been created (`created` is `true`)
+    /// and on every later login (`created` is `false`).
+    fn sync<'a>(
+        &'a self,
+        tx: &'a mut Transaction,
+        account_pk: &'a <<M::Account as Model>::Primary as Field>::Type,
+        claims: &'a CoreIdTokenClaims,
+        created: bool,
+    ) -> ClaimsFuture<'a>;
+}
+
+impl<M: AuthModels> AuthModule<M> {
+    /// Sets the mapper for the OpenID Connect provider's claims
+    ///
+    /// This can only be set once, returns the rejected mapper if it has already been set.
+    pub fn set_claims_mapper(
+        &self,
+        mapper: impl ClaimsMapper<M>,
+    ) -> Result<(), Box<dyn ClaimsMapper<M>>> {
+        self.claims_mapper.set(Box::new(mapper))
+    }
+}
+
+/// Uses the `preferred_username` claim as identifier
+pub(crate) fn default_identifier(claims: &CoreIdTokenClaims) -> ApiResult<String> {
+    claims
+        .preferred_username()
+        .map(|username| username.to_string())
+        .ok_or_else(|| ApiError::client_error("Missing claim: preferred_username").into())
+}
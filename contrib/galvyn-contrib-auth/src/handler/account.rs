@@ -0,0 +1,34 @@
+use crate::handler::schema::DeactivateAccountRequest;
+use crate::sessions::SessionAccount;
+use crate::{AuthModels, AuthModule};
+use galvyn_core::re_exports::axum::Json;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::Module;
+use galvyn_macros::post;
+use rorm::crud::query::QueryBuilder;
+use rorm::FieldAccess;
+
+/// Disables the logged-in account
+///
+/// The account is logged out of all its sessions.
+/// Only an admin can enable it again (see [`admin_enable_account`](super::admin_enable_account)).
+#[post("/account/deactivate", core_crate = "::galvyn_core")]
+pub async fn deactivate_account<M: AuthModels>(
+    SessionAccount { account_pk }: SessionAccount<M>,
+    Json(request): Json<DeactivateAccountRequest>,
+) -> ApiResult<()> {
+    let module = AuthModule::<M>::global();
+
+    let confirmed = QueryBuilder::new(&module.db, (M::account_pk(),))
+        .condition(M::account_id().equals(&request.identifier))
+        .optional()
+        .await?
+        .is_some_and(|(pk,)| pk == account_pk);
+    if !confirmed {
+        return Err(
+            ApiError::client_error("Identifier doesn't match the logged-in account").into(),
+        );
+    }
+
+    module.disable_account(&request.identifier).await
+}
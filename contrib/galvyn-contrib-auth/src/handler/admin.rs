@@ -0,0 +1,327 @@
+use crate::accounts;
+use crate::audit::{self, AuditContext, AuthEventInfo, AuthEventKind};
+use crate::handler::local::issue_password_reset_token;
+use crate::handler::schema::{
+    AdminAccountInfo, AdminCreateAccountRequest, AdminGetAccountsRequest, GetAuthEventsRequest,
+};
+use crate::handler::{DEFAULT_EVENT_LIMIT, MAX_EVENT_LIMIT};
+use crate::password::hash_password;
+use crate::sessions;
+use crate::{AuthModels, AuthModule};
+use galvyn_core::re_exports::axum::extract::{Path, Query};
+use galvyn_core::re_exports::axum::Json;
+use galvyn_core::session::Session;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::Module;
+use galvyn_macros::{delete, get, post};
+use rorm::crud::query::QueryBuilder;
+use rorm::crud::update::UpdateBuilder;
+use rorm::db::Transaction;
+use rorm::internal::field::Field;
+use rorm::{insert, FieldAccess, Model};
+
+/// The number of accounts returned by `GET /admin/accounts` if no limit is given
+const DEFAULT_LIMIT: u64 = 50;
+
+/// The maximum number of accounts returned by `GET /admin/accounts`
+const MAX_LIMIT: u64 = 100;
+
+#[get("/admin/accounts", core_crate = "::galvyn_core")]
+pub async fn admin_get_accounts<M: AuthModels>(
+    session: Session,
+    Query(request): Query<AdminGetAccountsRequest>,
+) -> ApiResult<Json<Vec<AdminAccountInfo>>> {
+    require_admin::<M>(&session).await?;
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let limit = request.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let offset = request.offset.unwrap_or(0);
+    let query = QueryBuilder::new(
+        &mut tx,
+        (M::account_pk(), M::account_id(), M::account_disabled_at()),
+    );
+    let accounts = match request.search {
+        Some(search) => {
+            query
+                .condition(M::account_id().like(format!("%{search}%")))
+                .order_asc(M::account_id())
+                .limit(limit)
+                .offset(offset)
+                .all()
+                .await?
+        }
+        None => {
+            query
+                .order_asc(M::account_id())
+                .limit(limit)
+                .offset(offset)
+                .all()
+                .await?
+        }
+    };
+
+    let mut infos = Vec::with_capacity(accounts.len());
+    for (account_pk, identifier, disabled_at) in accounts {
+        let oidc = QueryBuilder::new(&mut tx, (M::oidc_account_pk(),))
+            .condition(M::oidc_account_fm().equals(&account_pk))
+            .optional()
+            .await?
+            .is_some();
+        let local = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+            .condition(M::local_account_fm().equals(&account_pk))
+            .optional()
+            .await?
+            .is_some();
+        infos.push(AdminAccountInfo {
+            identifier,
+            disabled_at,
+            oidc,
+            local,
+        });
+    }
+
+    tx.commit().await?;
+    Ok(Json(infos))
+}
+
+#[post("/admin/accounts", core_crate = "::galvyn_core")]
+pub async fn admin_create_account<M: AuthModels>(
+    session: Session,
+    Json(request): Json<AdminCreateAccountRequest>,
+) -> ApiResult<()> {
+    require_admin::<M>(&session).await?;
+
+    let password = match request.password {
+        Some(password) if password.is_empty() => {
+            return Err(ApiError::client_error("Password must not be empty").into());
+        }
+        Some(password) => Some(hash_password(&password)?),
+        None => None,
+    };
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let existing = QueryBuilder::new(&mut tx, (M::account_pk(),))
+        .condition(M::account_id().equals(&request.identifier))
+        .optional()
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::client_error("Identifier is already taken").into());
+    }
+
+    let account_pk = insert!(&mut tx, M::Account)
+        .return_primary_key()
+        .single(&M::insertable_account(request.identifier))
+        .await?;
+
+    insert!(&mut tx, M::LocalAccount)
+        .return_nothing()
+        .single(&M::insertable_local_account(password, &account_pk))
+        .await?;
+
+    accounts::created::<M>(&mut tx, &account_pk).await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[post("/admin/accounts/{identifier}/disable", core_crate = "::galvyn_core")]
+pub async fn admin_disable_account<M: AuthModels>(
+    session: Session,
+    Path(identifier): Path<String>,
+) -> ApiResult<()> {
+    require_admin::<M>(&session).await?;
+    AuthModule::<M>::global().disable_account(&identifier).await
+}
+
+#[post("/admin/accounts/{identifier}/enable", core_crate = "::galvyn_core")]
+pub async fn admin_enable_account<M: AuthModels>(
+    session: Session,
+    Path(identifier): Path<String>,
+) -> ApiResult<()> {
+    require_admin::<M>(&session).await?;
+    AuthModule::<M>::global().enable_account(&identifier).await
+}
+
+#[get("/admin/accounts/{identifier}/events", core_crate = "::galvyn_core")]
+pub async fn admin_get_auth_events<M: AuthModels>(
+    session: Session,
+    Path(identifier): Path<String>,
+    Query(request): Query<GetAuthEventsRequest>,
+) -> ApiResult<Json<Vec<AuthEventInfo>>> {
+    require_admin::<M>(&session).await?;
+
+    let module = AuthModule::<M>::global();
+    let (account_pk,) = QueryBuilder::new(&module.db, (M::account_pk(),))
+        .condition(M::account_id().equals(&identifier))
+        .optional()
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+
+    let limit = request
+        .limit
+        .unwrap_or(DEFAULT_EVENT_LIMIT)
+        .min(MAX_EVENT_LIMIT);
+    let events = module.get_auth_events(&account_pk, limit).await?;
+    Ok(Json(events))
+}
+
+/// Removes a local account's password and sends it a password reset token
+///
+/// The account is logged out of all its sessions.
+#[post(
+    "/admin/accounts/{identifier}/reset-password",
+    core_crate = "::galvyn_core"
+)]
+pub async fn admin_reset_password<M: AuthModels>(
+    session: Session,
+    Path(identifier): Path<String>,
+) -> ApiResult<()> {
+    require_admin::<M>(&session).await?;
+
+    let module = AuthModule::<M>::global();
+    let delivery = module
+        .password_reset_delivery
+        .get()
+        .ok_or_else(|| ApiError::server_error("No password reset delivery has been set"))?;
+
+    let mut tx = module.db.start_transaction().await?;
+
+    let (account_pk,) = QueryBuilder::new(&mut tx, (M::account_pk(),))
+        .condition(M::account_id().equals(&identifier))
+        .optional()
+        .await?
+        .ok_or_else(|| ApiError::not_found("Account not found"))?;
+
+    let updated = UpdateBuilder::new(&mut tx)
+        .condition(M::local_account_fm().equals(&account_pk))
+        .set(M::local_account_password(), None)
+        .exec()
+        .await?;
+    if updated == 0 {
+        return Err(ApiError::client_error("Not a local account").into());
+    }
+
+    rorm::delete!(&mut tx, M::RefreshToken)
+        .condition(M::refresh_token_fm().equals(&account_pk))
+        .await?;
+    let token = issue_password_reset_token::<M>(&mut tx, &account_pk).await?;
+
+    tx.commit().await?;
+
+    module.revoke_sessions(&account_pk).await?;
+
+    delivery(identifier, token).await
+}
+
+/// Switches the session to another account
+///
+/// The admin's account is remembered in the session
+/// and restored by [`admin_end_impersonation`].
+/// Impersonation is only supported for cookie sessions, not for JWTs.
+#[post("/admin/impersonate/{identifier}", core_crate = "::galvyn_core")]
+pub async fn admin_impersonate<M: AuthModels>(
+    session: Session,
+    audit_context: AuditContext,
+    Path(identifier): Path<String>,
+) -> ApiResult<()> {
+    let admin_pk = require_admin::<M>(&session).await?;
+
+    if sessions::impersonator::<M>(&session).await?.is_some() {
+        return Err(ApiError::client_error("Already impersonating an account").into());
+    }
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (account_pk, disabled_at) =
+        QueryBuilder::new(&mut tx, (M::account_pk(), M::account_disabled_at()))
+            .condition(M::account_id().equals(&identifier))
+            .optional()
+            .await?
+            .ok_or_else(|| ApiError::not_found("Account not found"))?;
+    if disabled_at.is_some() {
+        return Err(ApiError::client_error("Account has been disabled").into());
+    }
+
+    let admin_id = account_identifier::<M>(&mut tx, &admin_pk).await?;
+    audit::record_by::<M>(
+        &mut tx,
+        &account_pk,
+        Some(admin_id),
+        AuthEventKind::ImpersonationStarted,
+        &audit_context,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    session.insert(sessions::IMPERSONATOR, &admin_pk).await?;
+    session.insert("account", &account_pk).await?;
+    sessions::cycle_id::<M>(&session).await?;
+    Ok(())
+}
+
+/// Switches the session back to the admin who started impersonating the current account
+#[delete("/admin/impersonate", core_crate = "::galvyn_core")]
+pub async fn admin_end_impersonation<M: AuthModels>(
+    session: Session,
+    audit_context: AuditContext,
+) -> ApiResult<()> {
+    let admin_pk = sessions::impersonator::<M>(&session)
+        .await?
+        .ok_or_else(|| ApiError::client_error("Not impersonating an account"))?;
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    session.insert("account", &admin_pk).await?;
+    session
+        .remove::<serde::de::IgnoredAny>(sessions::IMPERSONATOR)
+        .await?;
+    sessions::cycle_id::<M>(&session).await?;
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+    let admin_id = account_identifier::<M>(&mut tx, &admin_pk).await?;
+    audit::record_by::<M>(
+        &mut tx,
+        &account_pk,
+        Some(admin_id),
+        AuthEventKind::ImpersonationEnded,
+        &audit_context,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Gets the identifier of an account
+async fn account_identifier<M: AuthModels>(
+    tx: &mut Transaction,
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+) -> ApiResult<String> {
+    let (id,) = QueryBuilder::new(&mut *tx, (M::account_id(),))
+        .condition(M::account_pk().equals(account_pk))
+        .optional()
+        .await?
+        .ok_or("Account not found")?;
+    Ok(id)
+}
+
+/// Rejects the request unless the logged-in account has the admin role
+///
+/// Requests without a logged-in account are rejected with `401 Unauthorized`,
+/// requests whose account lacks the role with `403 Forbidden`.
+async fn require_admin<M: AuthModels>(
+    session: &Session,
+) -> ApiResult<<<M::Account as Model>::Primary as Field>::Type> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type = session
+        .get("account")
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("Not logged-in"))?;
+
+    let module = AuthModule::<M>::global();
+    if !module.has_role(&account_pk, &module.admin_role).await? {
+        return Err(ApiError::forbidden("Missing admin role").into());
+    }
+    Ok(account_pk)
+}
@@ -0,0 +1,91 @@
+use crate::handler::schema::{ApiKeyInfo, CreateApiKeyRequest, CreateApiKeyResponse};
+use crate::utils::{generate_token, hash_token, unix_now};
+use crate::{AuthModels, AuthModule};
+use galvyn_core::re_exports::axum::extract::Path;
+use galvyn_core::re_exports::axum::Json;
+use galvyn_core::session::Session;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::Module;
+use galvyn_macros::{delete, get, post};
+use rorm::crud::query::QueryBuilder;
+use rorm::internal::field::Field;
+use rorm::{and, insert, FieldAccess, Model};
+
+#[post("/api-keys", core_crate = "::galvyn_core")]
+pub async fn create_api_key<M: AuthModels>(
+    session: Session,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> ApiResult<Json<CreateApiKeyResponse>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let id = generate_token();
+    let key = generate_token();
+    let expires_at = request
+        .expires_in
+        .map(|expires_in| unix_now() + expires_in as i64);
+
+    insert!(&AuthModule::<M>::global().db, M::ApiKey)
+        .return_nothing()
+        .single(&M::insertable_api_key(
+            id.clone(),
+            request.label,
+            hash_token(&key),
+            request.scopes,
+            expires_at,
+            &account_pk,
+        ))
+        .await?;
+
+    Ok(Json(CreateApiKeyResponse { id, key }))
+}
+
+#[get("/api-keys", core_crate = "::galvyn_core")]
+pub async fn get_api_keys<M: AuthModels>(session: Session) -> ApiResult<Json<Vec<ApiKeyInfo>>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let keys = QueryBuilder::new(
+        &AuthModule::<M>::global().db,
+        (
+            M::api_key_id(),
+            M::api_key_label(),
+            M::api_key_scopes(),
+            M::api_key_expires_at(),
+        ),
+    )
+    .condition(M::api_key_fm().equals(&account_pk))
+    .all()
+    .await?;
+
+    Ok(Json(
+        keys.into_iter()
+            .map(|(id, label, scopes, expires_at)| ApiKeyInfo {
+                id,
+                label,
+                scopes: scopes.0,
+                expires_at,
+            })
+            .collect(),
+    ))
+}
+
+#[delete("/api-keys/{id}", core_crate = "::galvyn_core")]
+pub async fn delete_api_key<M: AuthModels>(
+    session: Session,
+    Path(id): Path<String>,
+) -> ApiResult<()> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let deleted = rorm::delete!(&AuthModule::<M>::global().db, M::ApiKey)
+        .condition(and![
+            M::api_key_fm().equals(&account_pk),
+            M::api_key_id().equals(&id),
+        ])
+        .await?;
+    if deleted == 0 {
+        return Err(ApiError::not_found("Api key not found").into());
+    }
+    Ok(())
+}
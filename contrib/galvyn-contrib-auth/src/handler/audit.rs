@@ -0,0 +1,36 @@
+use crate::audit::AuthEventInfo;
+use crate::handler::schema::GetAuthEventsRequest;
+use crate::{AuthModels, AuthModule};
+use galvyn_core::re_exports::axum::extract::Query;
+use galvyn_core::re_exports::axum::Json;
+use galvyn_core::session::Session;
+use galvyn_core::stuff::api_error::ApiResult;
+use galvyn_core::Module;
+use galvyn_macros::get;
+use rorm::internal::field::Field;
+use rorm::Model;
+
+/// The number of events returned if no limit is given
+pub(crate) const DEFAULT_EVENT_LIMIT: u64 = 50;
+
+/// The maximum number of events returned at once
+pub(crate) const MAX_EVENT_LIMIT: u64 = 500;
+
+/// Gets the logged-in account's most recent authentication events
+#[get("/events", core_crate = "::galvyn_core")]
+pub async fn get_auth_events<M: AuthModels>(
+    session: Session,
+    Query(request): Query<GetAuthEventsRequest>,
+) -> ApiResult<Json<Vec<AuthEventInfo>>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let limit = request
+        .limit
+        .unwrap_or(DEFAULT_EVENT_LIMIT)
+        .min(MAX_EVENT_LIMIT);
+    let events = AuthModule::<M>::global()
+        .get_auth_events(&account_pk, limit)
+        .await?;
+    Ok(Json(events))
+}
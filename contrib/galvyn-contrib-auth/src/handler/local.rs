@@ -1,19 +1,46 @@
+use crate::accounts;
+use crate::audit::{self, AuditContext, AuthEventKind};
+use crate::captcha;
+use crate::handler::finish_login;
+use crate::handler::schema::{
+    webauthn_schema, ConfirmLocalTotpRequest, EnrollLocalTotpRequest, EnrollLocalTotpResponse,
+    FinishPasswordResetRequest, FinishVerificationRequest, LinkLocalAccountRequest, LoginResponse,
+    RecoveryCodesResponse, RegisterLocalAccountRequest, RegisterLocalWebauthnRequest,
+    RequestPasswordResetRequest, RequestVerificationRequest, WebauthnKeyInfo,
+};
+use crate::password::hash_password;
+use crate::recovery;
+use crate::sessions;
+use crate::totp;
+use crate::utils::{generate_hashed_token, hash_token, unix_now};
 use crate::{AuthModels, AuthModule, MaybeAttestedPasskey};
+use galvyn_core::re_exports::axum::extract::Path;
 use galvyn_core::re_exports::axum::Json;
 use galvyn_core::session::Session;
-use galvyn_core::stuff::api_error::ApiResult;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::stuff::client_ip::ClientIp;
 use galvyn_core::Module;
-use galvyn_macros::{delete, put};
+use galvyn_macros::{delete, get, post, put};
 use rorm::crud::query::QueryBuilder;
 use rorm::crud::update::UpdateBuilder;
+use rorm::db::Transaction;
 use rorm::internal::field::Field;
+use rorm::prelude::ForeignModelByField;
+use rorm::{and, insert};
 use rorm::{FieldAccess, Model};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use webauthn_rs::prelude::{
+    AttestedPasskeyRegistration, CreationChallengeResponse, PasskeyRegistration,
+    RegisterPublicKeyCredential, Uuid,
+};
 
 type SetLocalPasswordRequest = String;
 
 #[put("/local/password", core_crate = "::galvyn_core")]
 pub async fn set_local_password<M: AuthModels>(
     session: Session,
+    audit_context: AuditContext,
     Json(request): Json<SetLocalPasswordRequest>,
 ) -> ApiResult<()> {
     let account_pk: <<M::Account as Model>::Primary as Field>::Type =
@@ -27,16 +54,32 @@ pub async fn set_local_password<M: AuthModels>(
         .await?
         .ok_or("User is not a local one")?;
 
-    // TODO: hashing
+    let password = hash_password(&request)?;
 
     UpdateBuilder::new(&mut tx)
         .condition(M::local_account_fm().equals(&account_pk))
-        .set(M::local_account_password(), Some(request))
+        .set(M::local_account_password(), Some(password))
         .exec()
         .await?;
 
+    // Sign out clients which might have been logged in by whoever knew the old password
+    rorm::delete!(&mut tx, M::RefreshToken)
+        .condition(M::refresh_token_fm().equals(&account_pk))
+        .await?;
+
+    audit::record::<M>(
+        &mut tx,
+        &account_pk,
+        AuthEventKind::PasswordChanged,
+        &audit_context,
+    )
+    .await?;
+
     tx.commit().await?;
 
+    let current = session.id().map(|id| id.to_string());
+    sessions::revoke_sessions_except::<M>(&account_pk, current.as_deref()).await?;
+
     Ok(())
 }
 
@@ -55,10 +98,9 @@ pub async fn delete_local_password<M: AuthModels>(session: Session) -> ApiResult
 
     let has_webauthn = QueryBuilder::new(&mut tx, (M::webauthn_key_key(),))
         .condition(M::webauthn_key_fm().equals(&local_pk))
-        .all()
+        .optional()
         .await?
-        .into_iter()
-        .any(|(key,)| matches!(key.0, MaybeAttestedPasskey::Attested(_)));
+        .is_some();
     if !has_webauthn {
         return Err("User has no other login method".into());
     }
@@ -72,3 +114,659 @@ pub async fn delete_local_password<M: AuthModels>(session: Session) -> ApiResult
     tx.commit().await?;
     Ok(())
 }
+
+#[post("/local/totp/enroll", core_crate = "::galvyn_core")]
+pub async fn enroll_local_totp<M: AuthModels>(
+    session: Session,
+    Json(request): Json<EnrollLocalTotpRequest>,
+) -> ApiResult<Json<EnrollLocalTotpResponse>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (_local_pk,) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?
+        .ok_or("User is not a local one")?;
+
+    tx.commit().await?;
+
+    let secret = totp::generate_secret()?;
+    let totp = totp::totp(
+        secret.clone(),
+        &AuthModule::<M>::global().local.totp_issuer,
+        request.label.clone(),
+    )?;
+
+    session
+        .insert(
+            "enroll_local_totp",
+            EnrollLocalTotpSessionData {
+                label: request.label,
+                secret,
+            },
+        )
+        .await?;
+
+    Ok(Json(EnrollLocalTotpResponse {
+        uri: totp.get_url(),
+        secret: totp.get_secret_base32(),
+    }))
+}
+
+#[derive(Serialize, Deserialize)]
+struct EnrollLocalTotpSessionData {
+    label: String,
+    secret: Vec<u8>,
+}
+
+#[post("/local/totp/confirm", core_crate = "::galvyn_core")]
+pub async fn confirm_local_totp<M: AuthModels>(
+    session: Session,
+    audit_context: AuditContext,
+    Json(request): Json<ConfirmLocalTotpRequest>,
+) -> ApiResult<Json<Option<RecoveryCodesResponse>>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let EnrollLocalTotpSessionData { label, secret } = session
+        .remove("enroll_local_totp")
+        .await?
+        .ok_or("No pending TOTP enrollment")?;
+
+    let Some(step) = totp::verify_totp(
+        &secret,
+        &AuthModule::<M>::global().local.totp_issuer,
+        &request.code,
+    ) else {
+        return Err(ApiError::client_error("Invalid TOTP code").into());
+    };
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (local_pk,) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?
+        .ok_or("User is not a local one")?;
+
+    insert!(&mut tx, M::TotpKey)
+        .return_nothing()
+        .single(&M::insertable_totp_key(label, secret, step, &local_pk))
+        .await?;
+
+    let codes = recovery::generate_if_missing::<M>(&mut tx, &local_pk).await?;
+
+    audit::record::<M>(
+        &mut tx,
+        &account_pk,
+        AuthEventKind::KeyEnrolled,
+        &audit_context,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(Json(codes.map(|codes| RecoveryCodesResponse { codes })))
+}
+
+#[post(
+    "/local/webauthn/start-register",
+    response_schema = webauthn_schema,
+    core_crate = "::galvyn_core"
+)]
+pub async fn register_local_webauthn<M: AuthModels>(
+    session: Session,
+    Json(request): Json<RegisterLocalWebauthnRequest>,
+) -> ApiResult<Json<CreationChallengeResponse>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (local_pk,) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?
+        .ok_or("User is not a local one")?;
+
+    let keys = QueryBuilder::new(&mut tx, (M::webauthn_key_label(), M::webauthn_key_key()))
+        .condition(M::webauthn_key_fm().equals(&local_pk))
+        .all()
+        .await?;
+
+    tx.commit().await?;
+
+    if keys.iter().any(|(label, _)| *label == request.label) {
+        return Err(ApiError::client_error("Label is already used by another key").into());
+    }
+    // A login can only challenge either attested or not attested keys
+    if keys
+        .iter()
+        .any(|(_, key)| matches!(key.0, MaybeAttestedPasskey::Attested(_)) != request.attested)
+    {
+        return Err(ApiError::client_error(
+            "Keys have to be either all attested or all not attested",
+        )
+        .into());
+    }
+    let exclude_credentials = keys
+        .into_iter()
+        .map(|(_, key)| key.0.cred_id().clone())
+        .collect();
+
+    let module = AuthModule::<M>::global();
+    let user_id = webauthn_user_id(&account_pk)?;
+    let (challenge, state) = if request.attested {
+        let (challenge, state) = module.webauthn.start_attested_passkey_registration(
+            user_id,
+            &request.label,
+            &request.label,
+            Some(exclude_credentials),
+            module.attestation_ca_list.clone(),
+            None,
+        )?;
+        (challenge, RegistrationState::Attested(state))
+    } else {
+        let (challenge, state) = module.webauthn.start_passkey_registration(
+            user_id,
+            &request.label,
+            &request.label,
+            Some(exclude_credentials),
+        )?;
+        (challenge, RegistrationState::NotAttested(state))
+    };
+
+    session
+        .insert(
+            "register_local_webauthn",
+            RegisterLocalWebauthnSessionData {
+                label: request.label,
+                state,
+            },
+        )
+        .await?;
+
+    Ok(Json(challenge))
+}
+
+#[derive(Serialize, Deserialize)]
+struct RegisterLocalWebauthnSessionData {
+    label: String,
+    state: RegistrationState,
+}
+
+#[derive(Serialize, Deserialize)]
+enum RegistrationState {
+    NotAttested(PasskeyRegistration),
+    Attested(AttestedPasskeyRegistration),
+}
+
+#[post(
+    "/local/webauthn/finish-register",
+    request_schema = webauthn_schema,
+    core_crate = "::galvyn_core"
+)]
+pub async fn finish_register_local_webauthn<M: AuthModels>(
+    session: Session,
+    audit_context: AuditContext,
+    Json(request): Json<RegisterPublicKeyCredential>,
+) -> ApiResult<Json<Option<RecoveryCodesResponse>>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let RegisterLocalWebauthnSessionData { label, state } = session
+        .remove("register_local_webauthn")
+        .await?
+        .ok_or("No pending passkey registration")?;
+
+    let webauthn = &AuthModule::<M>::global().webauthn;
+    let key = match state {
+        RegistrationState::NotAttested(state) => MaybeAttestedPasskey::NotAttested(
+            webauthn.finish_passkey_registration(&request, &state)?,
+        ),
+        RegistrationState::Attested(state) => MaybeAttestedPasskey::Attested(
+            webauthn.finish_attested_passkey_registration(&request, &state)?,
+        ),
+    };
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (local_pk,) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?
+        .ok_or("User is not a local one")?;
+
+    insert!(&mut tx, M::WebauthnKey)
+        .return_nothing()
+        .single(&M::insertable_webauthn_key(label, key, &local_pk))
+        .await?;
+
+    let codes = recovery::generate_if_missing::<M>(&mut tx, &local_pk).await?;
+
+    audit::record::<M>(
+        &mut tx,
+        &account_pk,
+        AuthEventKind::KeyEnrolled,
+        &audit_context,
+    )
+    .await?;
+
+    tx.commit().await?;
+    Ok(Json(codes.map(|codes| RecoveryCodesResponse { codes })))
+}
+
+#[post("/local/recovery-codes", core_crate = "::galvyn_core")]
+pub async fn regenerate_local_recovery_codes<M: AuthModels>(
+    session: Session,
+) -> ApiResult<Json<RecoveryCodesResponse>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (local_pk,) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?
+        .ok_or("User is not a local one")?;
+
+    let codes = recovery::generate::<M>(&mut tx, &local_pk).await?;
+
+    tx.commit().await?;
+    Ok(Json(RecoveryCodesResponse { codes }))
+}
+
+#[get("/local/webauthn", core_crate = "::galvyn_core")]
+pub async fn get_local_webauthn_keys<M: AuthModels>(
+    session: Session,
+) -> ApiResult<Json<Vec<WebauthnKeyInfo>>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (local_pk,) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?
+        .ok_or("User is not a local one")?;
+
+    let keys = QueryBuilder::new(&mut tx, (M::webauthn_key_label(), M::webauthn_key_key()))
+        .condition(M::webauthn_key_fm().equals(&local_pk))
+        .all()
+        .await?;
+
+    tx.commit().await?;
+    Ok(Json(
+        keys.into_iter()
+            .map(|(label, key)| WebauthnKeyInfo {
+                label,
+                attested: matches!(key.0, MaybeAttestedPasskey::Attested(_)),
+            })
+            .collect(),
+    ))
+}
+
+#[delete("/local/webauthn/{label}", core_crate = "::galvyn_core")]
+pub async fn delete_local_webauthn_key<M: AuthModels>(
+    session: Session,
+    Path(label): Path<String>,
+) -> ApiResult<()> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (local_pk, password) = QueryBuilder::new(
+        &mut tx,
+        (M::local_account_pk(), M::local_account_password()),
+    )
+    .condition(M::local_account_fm().equals(&account_pk))
+    .optional()
+    .await?
+    .ok_or("User is not a local one")?;
+
+    let keys = QueryBuilder::new(&mut tx, (M::webauthn_key_label(), M::webauthn_key_key()))
+        .condition(M::webauthn_key_fm().equals(&local_pk))
+        .all()
+        .await?;
+    if !keys.iter().any(|(key_label, _)| *key_label == label) {
+        return Err(ApiError::not_found("Key not found").into());
+    }
+
+    let has_other_login =
+        password.is_some() || keys.iter().any(|(key_label, _)| *key_label != label);
+    if !has_other_login {
+        return Err("User has no other login method".into());
+    }
+
+    rorm::delete!(&mut tx, M::WebauthnKey)
+        .condition(and![
+            M::webauthn_key_fm().equals(&local_pk),
+            M::webauthn_key_label().equals(&label),
+        ])
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Registers a new local account
+///
+/// If local accounts have to be verified, the new account is not logged in.
+/// Instead, a verification token is delivered and the response contains no tokens.
+#[post("/register", core_crate = "::galvyn_core")]
+pub async fn register_local_account<M: AuthModels>(
+    session: Session,
+    client_ip: ClientIp,
+    Json(request): Json<RegisterLocalAccountRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    let module = AuthModule::<M>::global();
+    if !module.local.registration {
+        return Err(ApiError::not_found("Registration is disabled").into());
+    }
+    let verification_delivery = if module.local.require_verification {
+        Some(
+            module
+                .verification_delivery
+                .get()
+                .ok_or_else(|| ApiError::server_error("No verification delivery has been set"))?,
+        )
+    } else {
+        None
+    };
+    if request.password.is_empty() {
+        return Err(ApiError::client_error("Password must not be empty").into());
+    }
+
+    captcha::require::<M>(request.captcha.as_deref(), client_ip).await?;
+
+    let password = hash_password(&request.password)?;
+
+    let mut tx = module.db.start_transaction().await?;
+
+    let existing = QueryBuilder::new(&mut tx, (M::account_pk(),))
+        .condition(M::account_id().equals(&request.identifier))
+        .optional()
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::client_error("Identifier is already taken").into());
+    }
+
+    let account_pk = insert!(&mut tx, M::Account)
+        .return_primary_key()
+        .single(&M::insertable_account(request.identifier.clone()))
+        .await?;
+
+    insert!(&mut tx, M::LocalAccount)
+        .return_nothing()
+        .single(&M::insertable_local_account(Some(password), &account_pk))
+        .await?;
+
+    accounts::created::<M>(&mut tx, &account_pk).await?;
+
+    let Some(delivery) = verification_delivery else {
+        tx.commit().await?;
+        return finish_login::<M>(&session, account_pk, false).await;
+    };
+
+    let token = set_verification_token::<M>(&mut tx, &account_pk).await?;
+
+    tx.commit().await?;
+
+    delivery(request.identifier, token).await?;
+    Ok(Json(LoginResponse {
+        access_token: None,
+        expires_in: None,
+        refresh_token: None,
+    }))
+}
+
+/// Adds local credentials to the logged-in account (for example one logging in using OIDC)
+///
+/// Passkeys can be added to the local account afterward, so the password is optional.
+#[post("/local/link", core_crate = "::galvyn_core")]
+pub async fn link_local_account<M: AuthModels>(
+    session: Session,
+    Json(request): Json<LinkLocalAccountRequest>,
+) -> ApiResult<()> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let password = match request.password {
+        Some(password) if password.is_empty() => {
+            return Err(ApiError::client_error("Password must not be empty").into());
+        }
+        Some(password) => Some(hash_password(&password)?),
+        None => None,
+    };
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let existing = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?;
+    if existing.is_some() {
+        return Err(ApiError::client_error("Account already has local credentials").into());
+    }
+
+    insert!(&mut tx, M::LocalAccount)
+        .return_nothing()
+        .single(&M::insertable_local_account(password, &account_pk))
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[post("/local/password/request-reset", core_crate = "::galvyn_core")]
+pub async fn request_password_reset<M: AuthModels>(
+    Json(request): Json<RequestPasswordResetRequest>,
+) -> ApiResult<()> {
+    let module = AuthModule::<M>::global();
+    let delivery = module
+        .password_reset_delivery
+        .get()
+        .ok_or_else(|| ApiError::server_error("No password reset delivery has been set"))?;
+
+    let mut tx = module.db.start_transaction().await?;
+
+    // Don't reveal whether the account exists
+    let Some((account_pk,)) = QueryBuilder::new(&mut tx, (M::account_pk(),))
+        .condition(M::account_id().equals(&request.identifier))
+        .optional()
+        .await?
+    else {
+        return Ok(());
+    };
+    if QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?
+        .is_none()
+    {
+        return Ok(());
+    }
+
+    let token = issue_password_reset_token::<M>(&mut tx, &account_pk).await?;
+
+    tx.commit().await?;
+
+    delivery(request.identifier, token).await
+}
+
+/// Replaces an account's password reset tokens with a new one
+///
+/// Only the token's hash is stored, the token itself is returned to be delivered.
+pub(crate) async fn issue_password_reset_token<M: AuthModels>(
+    tx: &mut Transaction,
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+) -> ApiResult<String> {
+    rorm::delete!(&mut *tx, M::PasswordResetToken)
+        .condition(M::password_reset_token_fm().equals(account_pk))
+        .await?;
+
+    let (token, token_hash) = generate_hashed_token();
+    let expires_at = unix_now()
+        + AuthModule::<M>::global()
+            .local
+            .password_reset_expiry
+            .as_secs() as i64;
+    insert!(&mut *tx, M::PasswordResetToken)
+        .return_nothing()
+        .single(&M::insertable_password_reset_token(
+            token_hash, expires_at, account_pk,
+        ))
+        .await?;
+    Ok(token)
+}
+
+#[post("/local/password/finish-reset", core_crate = "::galvyn_core")]
+pub async fn finish_password_reset<M: AuthModels>(
+    audit_context: AuditContext,
+    Json(request): Json<FinishPasswordResetRequest>,
+) -> ApiResult<()> {
+    if request.password.is_empty() {
+        return Err(ApiError::client_error("Password must not be empty").into());
+    }
+
+    let password = hash_password(&request.password)?;
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (account_fm,) = QueryBuilder::new(&mut tx, (M::password_reset_token_fm(),))
+        .condition(and![
+            M::password_reset_token_token_hash().equals(&hash_token(&request.token)),
+            M::password_reset_token_expires_at().greater_than(unix_now()),
+        ])
+        .optional()
+        .await?
+        .ok_or_else(|| ApiError::client_error("Invalid or expired token"))?;
+    let ForeignModelByField::Key(account_pk) = account_fm else {
+        return Err(ApiError::server_error("Foreign model has been queried as instance").into());
+    };
+
+    rorm::delete!(&mut tx, M::PasswordResetToken)
+        .condition(M::password_reset_token_fm().equals(&account_pk))
+        .await?;
+
+    // Sign out clients which might have been logged in by whoever knew the old password
+    rorm::delete!(&mut tx, M::RefreshToken)
+        .condition(M::refresh_token_fm().equals(&account_pk))
+        .await?;
+
+    UpdateBuilder::new(&mut tx)
+        .condition(M::local_account_fm().equals(&account_pk))
+        .set(M::local_account_password(), Some(password))
+        .set(M::local_account_failed_logins(), 0)
+        .set(M::local_account_locked_until(), None)
+        .exec()
+        .await?;
+
+    audit::record::<M>(
+        &mut tx,
+        &account_pk,
+        AuthEventKind::PasswordChanged,
+        &audit_context,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    AuthModule::<M>::global()
+        .revoke_sessions(&account_pk)
+        .await?;
+    Ok(())
+}
+
+#[post("/local/verification/request", core_crate = "::galvyn_core")]
+pub async fn request_verification<M: AuthModels>(
+    Json(request): Json<RequestVerificationRequest>,
+) -> ApiResult<()> {
+    let module = AuthModule::<M>::global();
+    let delivery = module
+        .verification_delivery
+        .get()
+        .ok_or_else(|| ApiError::server_error("No verification delivery has been set"))?;
+
+    let mut tx = module.db.start_transaction().await?;
+
+    // Don't reveal whether the account exists
+    let Some((account_pk,)) = QueryBuilder::new(&mut tx, (M::account_pk(),))
+        .condition(M::account_id().equals(&request.identifier))
+        .optional()
+        .await?
+    else {
+        return Ok(());
+    };
+    let Some((verified,)) = QueryBuilder::new(&mut tx, (M::local_account_verified(),))
+        .condition(M::local_account_fm().equals(&account_pk))
+        .optional()
+        .await?
+    else {
+        return Ok(());
+    };
+    if verified {
+        return Ok(());
+    }
+
+    let token = set_verification_token::<M>(&mut tx, &account_pk).await?;
+
+    tx.commit().await?;
+
+    delivery(request.identifier, token).await
+}
+
+#[post("/local/verification/finish", core_crate = "::galvyn_core")]
+pub async fn finish_verification<M: AuthModels>(
+    Json(request): Json<FinishVerificationRequest>,
+) -> ApiResult<()> {
+    let updated = UpdateBuilder::new(&AuthModule::<M>::global().db)
+        .condition(and![
+            M::local_account_verification_token_hash().equals(&hash_token(&request.token)),
+            M::local_account_verification_expires_at().greater_than(unix_now()),
+        ])
+        .set(M::local_account_verified(), true)
+        .set(M::local_account_verification_token_hash(), None)
+        .set(M::local_account_verification_expires_at(), None)
+        .exec()
+        .await?;
+    if updated == 0 {
+        return Err(ApiError::client_error("Invalid or expired token").into());
+    }
+    Ok(())
+}
+
+/// Replaces a local account's pending verification token with a new one
+///
+/// Only the token's hash is stored, the token itself is returned to be delivered.
+async fn set_verification_token<M: AuthModels>(
+    tx: &mut Transaction,
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+) -> ApiResult<String> {
+    let (token, token_hash) = generate_hashed_token();
+    let expires_at = unix_now()
+        + AuthModule::<M>::global()
+            .local
+            .verification_expiry
+            .as_secs() as i64;
+    UpdateBuilder::new(&mut *tx)
+        .condition(M::local_account_fm().equals(account_pk))
+        .set(M::local_account_verification_token_hash(), Some(token_hash))
+        .set(M::local_account_verification_expires_at(), Some(expires_at))
+        .exec()
+        .await?;
+    Ok(token)
+}
+
+/// Derives the stable user handle identifying an account to its authenticators
+fn webauthn_user_id<T: Serialize>(account_pk: &T) -> Result<Uuid, serde_json::Error> {
+    let hash = Sha256::digest(serde_json::to_vec(account_pk)?);
+    let mut bytes = [0; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    Ok(Uuid::from_bytes(bytes))
+}
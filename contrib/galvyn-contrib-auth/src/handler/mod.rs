@@ -1,24 +1,39 @@
+use crate::audit::{self, AuditContext, AuthEventKind};
+use crate::captcha;
 use crate::handler::schema::{
     GetLoginFlowsRequest, GetLoginFlowsResponse, LocalLoginFlow, LoginLocalPasswordRequest,
-    LoginLocalWebauthnRequest, OidcLoginFlow, PublicKeyCredential,
+    LoginLocalRecoveryCodeRequest, LoginLocalWebauthnRequest, LoginResponse, OidcLoginFlow,
 };
 use crate::models::AuthModels;
 use crate::module::AuthModule;
+use crate::password::{hash_password, verify_password, Verification};
+use crate::recovery;
+use crate::sessions;
+use crate::throttle::{self, ThrottleKeys};
+use crate::totp::verify_totp;
+use crate::utils::unix_now;
 use crate::MaybeAttestedPasskey;
 use galvyn_core::re_exports::axum::extract::Query;
+use galvyn_core::re_exports::axum::http::{header, HeaderValue};
 
 use galvyn_core::re_exports::axum::Json;
 use galvyn_core::session::Session;
-use galvyn_core::stuff::api_error::ApiResult;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::stuff::client_ip::ClientIp;
 use galvyn_core::Module;
 use galvyn_macros::{get, post};
 
 use rorm::crud::query::QueryBuilder;
+use rorm::crud::update::UpdateBuilder;
+use rorm::db::Transaction;
 use rorm::internal::field::foreign_model::FieldEq_ForeignModelByField_Borrowed;
-
-use rorm::FieldAccess;
+use rorm::internal::field::Field;
+use rorm::{and, FieldAccess, Model};
 use serde::{Deserialize, Serialize};
-use webauthn_rs::prelude::{AttestedPasskeyAuthentication, RequestChallengeResponse};
+use webauthn_rs::prelude::{
+    AttestedPasskeyAuthentication, PasskeyAuthentication, PublicKeyCredential,
+    RequestChallengeResponse,
+};
 
 #[cfg(feature = "oidc")]
 mod oidc;
@@ -27,6 +42,18 @@ pub use self::oidc::*;
 
 mod local;
 pub use self::local::*;
+mod account;
+pub use self::account::*;
+mod admin;
+pub use self::admin::*;
+mod api_key;
+pub use self::api_key::*;
+mod audit;
+pub use self::audit::*;
+mod session;
+pub use self::session::*;
+mod token;
+pub use self::token::*;
 mod schema;
 
 #[get("/login", core_crate = "::galvyn_core")]
@@ -56,51 +83,83 @@ pub async fn get_login_flow<M: AuthModels>(
     .optional()
     .await?;
 
-    let response = match (oidc, local) {
-        (Some(_), None) => GetLoginFlowsResponse::Oidc(OidcLoginFlow {}),
-        (None, Some((local_pk, password))) => {
-            let webauthn = QueryBuilder::new(&mut tx, (M::webauthn_key_key(),))
-                .condition(
-                    M::webauthn_key_fm()
-                        .equals::<_, FieldEq_ForeignModelByField_Borrowed>(&local_pk),
-                )
-                .all()
-                .await?
-                .into_iter()
-                .any(|(key,)| matches!(key.0, MaybeAttestedPasskey::Attested(_)));
-
-            GetLoginFlowsResponse::Local(LocalLoginFlow {
-                password: password.is_some(),
-                webauthn,
-            })
-        }
-        _ => return Err("Invalid account".into()),
+    if oidc.is_none() && local.is_none() {
+        return Err("Invalid account".into());
+    }
+
+    let local = if let Some((local_pk, password)) = local {
+        let webauthn = QueryBuilder::new(&mut tx, (M::webauthn_key_key(),))
+            .condition(
+                M::webauthn_key_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(&local_pk),
+            )
+            .optional()
+            .await?
+            .is_some();
+
+        let totp = QueryBuilder::new(&mut tx, (M::totp_key_secret(),))
+            .condition(
+                M::totp_key_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(&local_pk),
+            )
+            .optional()
+            .await?
+            .is_some();
+
+        Some(LocalLoginFlow {
+            password: password.is_some(),
+            webauthn,
+            totp,
+        })
+    } else {
+        None
     };
 
     tx.commit().await?;
-    Ok(Json(Some(response)))
+    Ok(Json(Some(GetLoginFlowsResponse {
+        oidc: oidc.map(|_| OidcLoginFlow {}),
+        local,
+    })))
 }
 
-#[post("/login/local/start-webauthn", core_crate = "::galvyn_core")]
+#[post(
+    "/login/local/start-webauthn",
+    response_schema = schema::webauthn_schema,
+    core_crate = "::galvyn_core"
+)]
 pub async fn login_local_webauthn<M: AuthModels>(
     session: Session,
+    client_ip: ClientIp,
     Json(request): Json<LoginLocalWebauthnRequest>,
 ) -> ApiResult<Json<RequestChallengeResponse>> {
     let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
 
-    let (account_pk,) = QueryBuilder::new(&mut tx, (M::account_pk(),))
+    let throttle_keys = ThrottleKeys::new(&request.identifier, client_ip);
+    throttle::check::<M>(&mut tx, &throttle_keys).await?;
+    captcha::require_after_failures::<M>(
+        &mut tx,
+        &throttle_keys,
+        request.captcha.as_deref(),
+        client_ip,
+    )
+    .await?;
+
+    // Whether the account is disabled is only revealed after the key has been verified
+    let Some((account_pk,)) = QueryBuilder::new(&mut tx, (M::account_pk(),))
         .condition(M::account_id().equals(&request.identifier))
         .optional()
         .await?
-        .ok_or("Account not found")?;
+    else {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSKEY).await;
+    };
 
-    let (local_account_pk,) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
+    let Some((local_account_pk,)) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
         .condition(
             M::local_account_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(&account_pk),
         )
         .optional()
         .await?
-        .ok_or("Not a local account")?;
+    else {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSKEY).await;
+    };
 
     let keys = QueryBuilder::new(&mut tx, (M::webauthn_key_key(),))
         .condition(
@@ -109,17 +168,26 @@ pub async fn login_local_webauthn<M: AuthModels>(
         )
         .all()
         .await?;
-    let keys = keys
-        .into_iter()
-        .filter_map(|(json,)| match json.0 {
-            MaybeAttestedPasskey::NotAttested(_) => None,
-            MaybeAttestedPasskey::Attested(key) => Some(key),
-        })
-        .collect::<Vec<_>>();
+    let mut attested = Vec::new();
+    let mut not_attested = Vec::new();
+    for (json,) in keys {
+        match json.0 {
+            MaybeAttestedPasskey::NotAttested(key) => not_attested.push(key),
+            MaybeAttestedPasskey::Attested(key) => attested.push(key),
+        }
+    }
+    if attested.is_empty() && not_attested.is_empty() {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSKEY).await;
+    }
 
-    let (challenge, state) = AuthModule::<M>::global()
-        .webauthn
-        .start_attested_passkey_authentication(&keys)?;
+    let webauthn = &AuthModule::<M>::global().webauthn;
+    let (challenge, state) = if not_attested.is_empty() {
+        let (challenge, state) = webauthn.start_attested_passkey_authentication(&attested)?;
+        (challenge, AuthenticationState::Attested(state))
+    } else {
+        let (challenge, state) = webauthn.start_passkey_authentication(&not_attested)?;
+        (challenge, AuthenticationState::NotAttested(state))
+    };
 
     tx.commit().await?;
 
@@ -128,6 +196,7 @@ pub async fn login_local_webauthn<M: AuthModels>(
             "login_local_webauthn",
             LoginLocalWebauthnSessionData {
                 identifier: request.identifier,
+                remember_me: request.remember_me,
                 state,
             },
         )
@@ -139,38 +208,77 @@ pub async fn login_local_webauthn<M: AuthModels>(
 #[derive(Serialize, Deserialize)]
 struct LoginLocalWebauthnSessionData {
     identifier: String,
-    state: AttestedPasskeyAuthentication,
+    remember_me: bool,
+    state: AuthenticationState,
+}
+
+#[derive(Serialize, Deserialize)]
+enum AuthenticationState {
+    NotAttested(PasskeyAuthentication),
+    Attested(AttestedPasskeyAuthentication),
 }
 
-#[post("/login/local/finish-webauthn", core_crate = "::galvyn_core")]
+#[post(
+    "/login/local/finish-webauthn",
+    request_schema = schema::webauthn_schema,
+    core_crate = "::galvyn_core"
+)]
 pub async fn finish_login_local_webauthn<M: AuthModels>(
     session: Session,
+    client_ip: ClientIp,
+    audit_context: AuditContext,
     Json(request): Json<PublicKeyCredential>,
-) -> ApiResult<()> {
-    let LoginLocalWebauthnSessionData { identifier, state } = session
+) -> ApiResult<Json<LoginResponse>> {
+    let LoginLocalWebauthnSessionData {
+        identifier,
+        remember_me,
+        state,
+    } = session
         .remove("login_local_webauthn")
         .await?
         .ok_or("Bad Request")?;
 
-    let authentication_result = AuthModule::<M>::global()
-        .webauthn
-        .finish_attested_passkey_authentication(&request.0, &state)?;
-
     let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
 
-    let (account_pk,) = QueryBuilder::new(&mut tx, (M::account_pk(),))
-        .condition(M::account_id().equals(&identifier))
-        .optional()
-        .await?
-        .ok_or("Account not found")?;
+    let throttle_keys = ThrottleKeys::new(&identifier, client_ip);
+    throttle::check::<M>(&mut tx, &throttle_keys).await?;
 
-    let (local_account_pk,) = QueryBuilder::new(&mut tx, (M::local_account_pk(),))
-        .condition(
-            M::local_account_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(&account_pk),
-        )
-        .optional()
-        .await?
-        .ok_or("Not a local account")?;
+    let webauthn = &AuthModule::<M>::global().webauthn;
+    let authentication_result = match state {
+        AuthenticationState::NotAttested(state) => {
+            webauthn.finish_passkey_authentication(&request, &state)
+        }
+        AuthenticationState::Attested(state) => {
+            webauthn.finish_attested_passkey_authentication(&request, &state)
+        }
+    };
+    let authentication_result = match authentication_result {
+        Ok(authentication_result) => authentication_result,
+        Err(_) => {
+            audit::record_failed_login::<M>(&mut tx, &identifier, &audit_context).await?;
+            return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSKEY).await;
+        }
+    };
+
+    let Some((account_pk, disabled_at)) =
+        QueryBuilder::new(&mut tx, (M::account_pk(), M::account_disabled_at()))
+            .condition(M::account_id().equals(&identifier))
+            .optional()
+            .await?
+    else {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSKEY).await;
+    };
+
+    let Some((local_account_pk, verified)) = QueryBuilder::new(
+        &mut tx,
+        (M::local_account_pk(), M::local_account_verified()),
+    )
+    .condition(M::local_account_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(&account_pk))
+    .optional()
+    .await?
+    else {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSKEY).await;
+    };
 
     let keys = QueryBuilder::new(&mut tx, (M::webauthn_key_key(),))
         .condition(
@@ -179,61 +287,431 @@ pub async fn finish_login_local_webauthn<M: AuthModels>(
         )
         .all()
         .await?;
-    let _used_key = keys
-        .into_iter()
-        .find_map(|(json,)| match json.0 {
-            MaybeAttestedPasskey::NotAttested(_) => None,
-            MaybeAttestedPasskey::Attested(key) => {
-                (key.cred_id() == authentication_result.cred_id()).then_some(key)
-            }
-        })
-        .ok_or("Used unknown key")?;
+    if !keys
+        .iter()
+        .any(|(json,)| json.0.cred_id() == authentication_result.cred_id())
+    {
+        audit::record_failed_login::<M>(&mut tx, &identifier, &audit_context).await?;
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSKEY).await;
+    }
 
-    tx.commit().await?;
+    if disabled_at.is_some() {
+        return Err(ApiError::forbidden("Account has been disabled").into());
+    }
+    if !verified && AuthModule::<M>::global().local.require_verification {
+        return Err(ApiError::forbidden("Account has not been verified").into());
+    }
 
-    session.insert("account", account_pk).await?;
+    throttle::reset::<M>(&mut tx, &throttle_keys).await?;
 
-    Ok(())
+    audit::record::<M>(
+        &mut tx,
+        &account_pk,
+        AuthEventKind::LoginSucceeded,
+        &audit_context,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    finish_login::<M>(&session, account_pk, remember_me).await
 }
 
 #[post("/login/local/password", core_crate = "::galvyn_core")]
 pub async fn login_local_password<M: AuthModels>(
     session: Session,
+    client_ip: ClientIp,
+    audit_context: AuditContext,
     Json(request): Json<LoginLocalPasswordRequest>,
-) -> ApiResult<()> {
+) -> ApiResult<Json<LoginResponse>> {
     let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
 
-    let (account_pk,) = QueryBuilder::new(&mut tx, (M::account_pk(),))
-        .condition(M::account_id().equals(&request.identifier))
+    let throttle_keys = ThrottleKeys::new(&request.identifier, client_ip);
+    throttle::check::<M>(&mut tx, &throttle_keys).await?;
+    captcha::require_after_failures::<M>(
+        &mut tx,
+        &throttle_keys,
+        request.captcha.as_deref(),
+        client_ip,
+    )
+    .await?;
+
+    let Some((account_pk, disabled_at)) =
+        QueryBuilder::new(&mut tx, (M::account_pk(), M::account_disabled_at()))
+            .condition(M::account_id().equals(&request.identifier))
+            .optional()
+            .await?
+    else {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSWORD).await;
+    };
+
+    let Some((local_account_pk, local_account_password, failed_logins, locked_until, verified)) =
+        QueryBuilder::new(
+            &mut tx,
+            (
+                M::local_account_pk(),
+                M::local_account_password(),
+                M::local_account_failed_logins(),
+                M::local_account_locked_until(),
+                M::local_account_verified(),
+            ),
+        )
+        .condition(
+            M::local_account_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(&account_pk),
+        )
         .optional()
         .await?
-        .ok_or("Account not found")?;
+    else {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_PASSWORD).await;
+    };
+
+    let now = unix_now();
+    if let Some(locked_until) = locked_until.filter(|locked_until| *locked_until > now) {
+        return Err(ApiError::too_many_requests("Account is locked")
+            .with_header(header::RETRY_AFTER, HeaderValue::from(locked_until - now))
+            .into());
+    }
 
-    let (local_account_password,) = QueryBuilder::new(&mut tx, (M::local_account_password(),))
+    let verification = match &local_account_password {
+        Some(hash) => verify_password(&request.password, hash)?,
+        None => Verification::Invalid,
+    };
+    let failure = if verification == Verification::Invalid {
+        Some(INVALID_PASSWORD)
+    } else {
+        let totp_keys = QueryBuilder::new(
+            &mut tx,
+            (
+                M::totp_key_pk(),
+                M::totp_key_secret(),
+                M::totp_key_last_used_step(),
+            ),
+        )
+        .condition(
+            M::totp_key_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(&local_account_pk),
+        )
+        .all()
+        .await?;
+        if totp_keys.is_empty() {
+            None
+        } else {
+            let code = request
+                .totp
+                .as_deref()
+                .ok_or_else(|| ApiError::client_error("Missing TOTP code"))?;
+            let issuer = &AuthModule::<M>::global().local.totp_issuer;
+            let mut valid = false;
+            for (key_pk, secret, last_used_step) in &totp_keys {
+                let Some(step) = verify_totp(secret, issuer, code) else {
+                    continue;
+                };
+                if step <= *last_used_step {
+                    continue;
+                }
+
+                // Only one of several concurrent logins may use the code
+                let updated = UpdateBuilder::new(&mut tx)
+                    .condition(and![
+                        M::totp_key_pk().equals(key_pk),
+                        M::totp_key_last_used_step().less_than(step),
+                    ])
+                    .set(M::totp_key_last_used_step(), step)
+                    .exec()
+                    .await?;
+                if updated > 0 {
+                    valid = true;
+                    break;
+                }
+            }
+            (!valid).then_some("Invalid TOTP code")
+        }
+    };
+    if let Some(failure) = failure {
+        record_local_failure::<M>(&mut tx, &account_pk).await?;
+        audit::record::<M>(
+            &mut tx,
+            &account_pk,
+            AuthEventKind::LoginFailed,
+            &audit_context,
+        )
+        .await?;
+        return reject_login::<M, _>(tx, &throttle_keys, failure).await;
+    }
+
+    // Don't reveal whether the account is disabled or unverified to someone without its credentials
+    if disabled_at.is_some() {
+        return Err(ApiError::forbidden("Account has been disabled").into());
+    }
+    if !verified && AuthModule::<M>::global().local.require_verification {
+        return Err(ApiError::forbidden("Account has not been verified").into());
+    }
+
+    if failed_logins != 0 || locked_until.is_some() {
+        UpdateBuilder::new(&mut tx)
+            .condition(
+                M::local_account_fm()
+                    .equals::<_, FieldEq_ForeignModelByField_Borrowed>(&account_pk),
+            )
+            .set(M::local_account_failed_logins(), 0)
+            .set(M::local_account_locked_until(), None)
+            .exec()
+            .await?;
+    }
+
+    if verification == Verification::Outdated {
+        UpdateBuilder::new(&mut tx)
+            .condition(
+                M::local_account_fm()
+                    .equals::<_, FieldEq_ForeignModelByField_Borrowed>(&account_pk),
+            )
+            .set(
+                M::local_account_password(),
+                Some(hash_password(&request.password)?),
+            )
+            .exec()
+            .await?;
+    }
+
+    throttle::reset::<M>(&mut tx, &throttle_keys).await?;
+
+    audit::record::<M>(
+        &mut tx,
+        &account_pk,
+        AuthEventKind::LoginSucceeded,
+        &audit_context,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    finish_login::<M>(&session, account_pk, request.remember_me).await
+}
+
+#[post("/login/local/recovery", core_crate = "::galvyn_core")]
+pub async fn login_local_recovery_code<M: AuthModels>(
+    session: Session,
+    client_ip: ClientIp,
+    audit_context: AuditContext,
+    Json(request): Json<LoginLocalRecoveryCodeRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let throttle_keys = ThrottleKeys::new(&request.identifier, client_ip);
+    throttle::check::<M>(&mut tx, &throttle_keys).await?;
+    captcha::require_after_failures::<M>(
+        &mut tx,
+        &throttle_keys,
+        request.captcha.as_deref(),
+        client_ip,
+    )
+    .await?;
+
+    let Some((account_pk, disabled_at)) =
+        QueryBuilder::new(&mut tx, (M::account_pk(), M::account_disabled_at()))
+            .condition(M::account_id().equals(&request.identifier))
+            .optional()
+            .await?
+    else {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_RECOVERY_CODE).await;
+    };
+
+    let Some((local_account_pk, local_account_password, failed_logins, locked_until, verified)) =
+        QueryBuilder::new(
+            &mut tx,
+            (
+                M::local_account_pk(),
+                M::local_account_password(),
+                M::local_account_failed_logins(),
+                M::local_account_locked_until(),
+                M::local_account_verified(),
+            ),
+        )
         .condition(
             M::local_account_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(&account_pk),
         )
         .optional()
         .await?
-        .ok_or("Not a local account")?;
+    else {
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_RECOVERY_CODE).await;
+    };
+
+    let now = unix_now();
+    if let Some(locked_until) = locked_until.filter(|locked_until| *locked_until > now) {
+        return Err(ApiError::too_many_requests("Account is locked")
+            .with_header(header::RETRY_AFTER, HeaderValue::from(locked_until - now))
+            .into());
+    }
+
+    let password_valid = match &local_account_password {
+        Some(hash) => verify_password(&request.password, hash)? != Verification::Invalid,
+        None => false,
+    };
+    // Don't use up the code if the password is wrong
+    if !password_valid || !recovery::consume::<M>(&mut tx, &local_account_pk, &request.code).await?
+    {
+        record_local_failure::<M>(&mut tx, &account_pk).await?;
+        audit::record::<M>(
+            &mut tx,
+            &account_pk,
+            AuthEventKind::LoginFailed,
+            &audit_context,
+        )
+        .await?;
+        return reject_login::<M, _>(tx, &throttle_keys, INVALID_RECOVERY_CODE).await;
+    }
 
-    let local_account_password = local_account_password.ok_or("Account has no password")?;
-    // TODO: hashing
-    if local_account_password != request.password {
-        return Err("Passwords do not match".into());
+    if disabled_at.is_some() {
+        return Err(ApiError::forbidden("Account has been disabled").into());
+    }
+    if !verified && AuthModule::<M>::global().local.require_verification {
+        return Err(ApiError::forbidden("Account has not been verified").into());
     }
 
-    // TODO: 2nd factor
+    if failed_logins != 0 || locked_until.is_some() {
+        UpdateBuilder::new(&mut tx)
+            .condition(
+                M::local_account_fm()
+                    .equals::<_, FieldEq_ForeignModelByField_Borrowed>(&account_pk),
+            )
+            .set(M::local_account_failed_logins(), 0)
+            .set(M::local_account_locked_until(), None)
+            .exec()
+            .await?;
+    }
+
+    throttle::reset::<M>(&mut tx, &throttle_keys).await?;
+
+    audit::record::<M>(
+        &mut tx,
+        &account_pk,
+        AuthEventKind::LoginSucceeded,
+        &audit_context,
+    )
+    .await?;
 
     tx.commit().await?;
 
-    session.insert("account", account_pk).await?;
+    finish_login::<M>(&session, account_pk, false).await
+}
 
-    Ok(())
+/// The error for an unknown identifier or invalid credentials of a passkey login
+const INVALID_PASSKEY: &str = "Invalid identifier or passkey";
+
+/// The error for an unknown identifier or invalid credentials of a password login
+const INVALID_PASSWORD: &str = "Invalid identifier or password";
+
+/// The error for an unknown identifier or invalid credentials of a recovery code login
+const INVALID_RECOVERY_CODE: &str = "Invalid identifier, password or recovery code";
+
+/// Counts a failed login of a local account and locks it once the lockout threshold is reached
+///
+/// The counter is only written if it hasn't changed since it has been read,
+/// so concurrent failures can't overwrite each other's increment.
+async fn record_local_failure<M: AuthModels>(
+    tx: &mut Transaction,
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+) -> ApiResult<()> {
+    let lockout = AuthModule::<M>::global().local.lockout;
+    loop {
+        let (failed_logins, locked_until) = QueryBuilder::new(
+            &mut *tx,
+            (
+                M::local_account_failed_logins(),
+                M::local_account_locked_until(),
+            ),
+        )
+        .condition(
+            M::local_account_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(account_pk),
+        )
+        .optional()
+        .await?
+        .ok_or("Local account has been deleted")?;
+
+        let failures = failed_logins + 1;
+        let (failures, locked_until) = if lockout.threshold > 0 && failures >= lockout.threshold {
+            (0, Some(unix_now() + lockout.duration.as_secs() as i64))
+        } else {
+            (failures, locked_until)
+        };
+
+        let updated = UpdateBuilder::new(&mut *tx)
+            .condition(and![
+                M::local_account_fm().equals::<_, FieldEq_ForeignModelByField_Borrowed>(account_pk),
+                M::local_account_failed_logins().equals(failed_logins),
+            ])
+            .set(M::local_account_failed_logins(), failures)
+            .set(M::local_account_locked_until(), locked_until)
+            .exec()
+            .await?;
+        if updated > 0 {
+            return Ok(());
+        }
+    }
+}
+
+/// Rejects a login attempt, counting it as a failure for the rate limit
+///
+/// Unknown identifiers and invalid credentials are rejected the same way
+/// to not reveal which identifiers exist.
+async fn reject_login<M: AuthModels, T>(
+    tx: Transaction,
+    throttle_keys: &ThrottleKeys,
+    message: &'static str,
+) -> ApiResult<T> {
+    tx.commit().await?;
+    throttle::record_failure::<M>(throttle_keys).await?;
+    Err(ApiError::client_error(message).into())
+}
+
+/// Logs an account in after it has been authenticated successfully
+///
+/// If JWTs are configured, this issues an access token instead of storing the account in the session.
+/// `remember_me` only affects sessions, since the refresh tokens are long-lived anyway.
+pub(crate) async fn finish_login<M: AuthModels>(
+    session: &Session,
+    account_pk: <<M::Account as Model>::Primary as Field>::Type,
+    remember_me: bool,
+) -> ApiResult<Json<LoginResponse>> {
+    if let Some(response) = issue_tokens::<M>(&account_pk).await? {
+        return Ok(Json(response));
+    }
+
+    sessions::login::<M>(session, account_pk, remember_me).await?;
+    Ok(Json(LoginResponse {
+        access_token: None,
+        expires_in: None,
+        refresh_token: None,
+    }))
+}
+
+/// Issues an access token and a refresh token for an account
+///
+/// Returns `None` if JWTs are not configured.
+pub(crate) async fn issue_tokens<M: AuthModels>(
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+) -> ApiResult<Option<LoginResponse>> {
+    let module = AuthModule::<M>::global();
+    let Some(access_token) = module.issue_jwt(account_pk)? else {
+        return Ok(None);
+    };
+    Ok(Some(LoginResponse {
+        access_token: Some(access_token),
+        expires_in: module.jwt.as_ref().map(|jwt| jwt.expiry.as_secs()),
+        refresh_token: module.issue_refresh_token(account_pk).await?,
+    }))
 }
 
 #[post("/logout", core_crate = "::galvyn_core")]
-pub async fn logout(session: Session) -> ApiResult<()> {
-    session.remove::<serde::de::IgnoredAny>("account").await?;
+pub async fn logout<M: AuthModels>(session: Session, audit_context: AuditContext) -> ApiResult<()> {
+    let account_pk: Option<<<M::Account as Model>::Primary as Field>::Type> =
+        session.get("account").await?;
+
+    sessions::logout::<M>(&session).await?;
+
+    if let Some(account_pk) = account_pk {
+        let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+        audit::record::<M>(&mut tx, &account_pk, AuthEventKind::Logout, &audit_context).await?;
+        tx.commit().await?;
+    }
     Ok(())
 }
@@ -1,13 +1,20 @@
+use crate::accounts;
+use crate::audit::{self, AuditContext, AuthEventKind};
+use crate::backchannel;
+use crate::claims;
 use crate::handler::schema::FinishLoginOidcRequest;
+use crate::sessions;
 use crate::{AuthModels, AuthModule};
 use galvyn_core::re_exports::axum::extract::Query;
 use galvyn_core::re_exports::axum::response::Redirect;
 use galvyn_core::session::Session;
-use galvyn_core::stuff::api_error::ApiResult;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::stuff::rorm_ext::retry_transaction;
 use galvyn_core::Module;
 use galvyn_macros::post;
 use openidconnect::core::CoreAuthenticationFlow;
 use openidconnect::reqwest::async_http_client;
+use openidconnect::url::form_urlencoded;
 use openidconnect::OAuth2TokenResponse;
 use openidconnect::TokenResponse;
 use openidconnect::{
@@ -15,12 +22,30 @@ use openidconnect::{
 };
 use rorm::crud::query::QueryBuilder;
 use rorm::insert;
+use rorm::internal::field::Field;
 use rorm::prelude::ForeignModelByField;
-use rorm::FieldAccess;
+use rorm::{FieldAccess, Model};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[post("/login/oidc/start", core_crate = "::galvyn_core")]
 pub async fn login_oidc<M: AuthModels>(session: Session) -> ApiResult<Redirect> {
+    start_oidc::<M>(&session, false).await
+}
+
+/// Links an account at the OpenID Connect provider to the logged-in account (for example a local one)
+///
+/// The provider redirects back to the same endpoint as for [`login_oidc`].
+#[post("/link/oidc/start", core_crate = "::galvyn_core")]
+pub async fn link_oidc<M: AuthModels>(session: Session) -> ApiResult<Redirect> {
+    let _account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    start_oidc::<M>(&session, true).await
+}
+
+/// Redirects to the OpenID Connect provider
+async fn start_oidc<M: AuthModels>(session: &Session, link: bool) -> ApiResult<Redirect> {
     let (pkce_code_challenge, pkce_code_verifier) = PkceCodeChallenge::new_random_sha256();
 
     let request = AuthModule::<M>::global()
@@ -42,6 +67,7 @@ pub async fn login_oidc<M: AuthModels>(session: Session) -> ApiResult<Redirect>
                 csrf_token,
                 pkce_code_verifier,
                 nonce,
+                link,
             },
         )
         .await?;
@@ -54,21 +80,26 @@ struct LoginOidcSessionData {
     csrf_token: CsrfToken,
     pkce_code_verifier: PkceCodeVerifier,
     nonce: Nonce,
+    /// Whether to link the OIDC account to the logged-in account instead of logging in
+    link: bool,
 }
 
-#[post("/login/oidc/finish", core_crate = "::galvyn_core")]
+#[post(
+    "/login/oidc/finish",
+    experimental = true,
+    core_crate = "::galvyn_core"
+)]
 pub async fn finish_login_oidc<M: AuthModels>(
     session: Session,
+    audit_context: AuditContext,
     Query(request): Query<FinishLoginOidcRequest>,
 ) -> ApiResult<Redirect> {
     let LoginOidcSessionData {
         csrf_token,
         pkce_code_verifier,
         nonce,
-    } = session
-        .remove("oidc_login_data")
-        .await?
-        .ok_or("Bad Request")?;
+        link,
+    } = session.remove("login_oidc").await?.ok_or("Bad Request")?;
 
     if request.state.secret() != csrf_token.secret() {
         return Err("Bad Request".into());
@@ -94,44 +125,164 @@ pub async fn finish_login_oidc<M: AuthModels>(
         }
     }
 
-    // TODO: extract claims
-    let Some(oidc_id) = claims.preferred_username().map(|x| x.to_string()) else {
-        return Err("Missing claim: preferred_username".into());
-    };
+    let subject = claims.subject().as_str().to_string();
+    let sid = backchannel::session_id_claim(id_token).unwrap_or_default();
 
-    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+    let oidc_id = claims::default_identifier(claims)?;
+
+    if link {
+        let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+            session.get("account").await?.ok_or("Not logged-in")?;
 
-    let account_pk = if let Some((account_fm,)) =
-        QueryBuilder::new(&mut tx, (M::oidc_account_fm(),))
+        let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+        let linked = QueryBuilder::new(&mut tx, (M::oidc_account_pk(),))
             .condition(M::oidc_account_id().equals(&oidc_id))
             .optional()
-            .await?
-    {
-        // TODO: update account with claims
-
-        match account_fm {
-            ForeignModelByField::Key(x) => x,
-            ForeignModelByField::Instance(_) => unreachable!(),
+            .await?;
+        if linked.is_some() {
+            return Err(ApiError::client_error("OIDC account is already linked").into());
         }
-    } else {
-        // TODO: create account with claims
 
-        let account_pk = insert!(&mut tx, M::Account)
-            .return_primary_key()
-            .single(&M::insertable_account(oidc_id.clone()))
+        let existing = QueryBuilder::new(&mut tx, (M::oidc_account_pk(),))
+            .condition(M::oidc_account_fm().equals(&account_pk))
+            .optional()
             .await?;
+        if existing.is_some() {
+            return Err(ApiError::client_error("Account already has an OIDC account").into());
+        }
 
         insert!(&mut tx, M::OidcAccount)
             .return_nothing()
             .single(&M::insertable_oidc_account(oidc_id, &account_pk))
             .await?;
 
-        account_pk
+        tx.commit().await?;
+        return Ok(Redirect::temporary("/"));
+    }
+
+    let mapper = AuthModule::<M>::global().claims_mapper.get();
+    let identifier = match mapper {
+        Some(mapper) => mapper.identifier(claims)?,
+        None => oidc_id.clone(),
     };
+    let claims = Arc::new(claims.clone());
+
+    let account_pk = retry_transaction(&AuthModule::<M>::global().db, |tx| {
+        let oidc_id = oidc_id.clone();
+        let identifier = identifier.clone();
+        let claims = claims.clone();
+        Box::pin(async move {
+            let (account_pk, created) = if let Some((account_fm,)) =
+                QueryBuilder::new(&mut *tx, (M::oidc_account_fm(),))
+                    .condition(M::oidc_account_id().equals(&oidc_id))
+                    .optional()
+                    .await?
+            {
+                let account_pk = match account_fm {
+                    ForeignModelByField::Key(x) => x,
+                    ForeignModelByField::Instance(_) => unreachable!(),
+                };
+                (account_pk, false)
+            } else {
+                let account_pk = insert!(&mut *tx, M::Account)
+                    .return_primary_key()
+                    .single(&M::insertable_account(identifier))
+                    .await?;
+
+                insert!(&mut *tx, M::OidcAccount)
+                    .return_nothing()
+                    .single(&M::insertable_oidc_account(oidc_id, &account_pk))
+                    .await?;
+
+                accounts::created::<M>(&mut *tx, &account_pk).await?;
 
+                (account_pk, true)
+            };
+
+            if let Some(mapper) = mapper {
+                mapper.sync(&mut *tx, &account_pk, &claims, created).await?;
+            }
+            Ok(account_pk)
+        })
+    })
+    .await?;
+
+    if AuthModule::<M>::global()
+        .is_account_disabled(&account_pk)
+        .await?
+    {
+        return Err(ApiError::forbidden("Account has been disabled").into());
+    }
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+    audit::record::<M>(
+        &mut tx,
+        &account_pk,
+        AuthEventKind::LoginSucceeded,
+        &audit_context,
+    )
+    .await?;
     tx.commit().await?;
 
-    session.insert("account", account_pk).await?;
+    sessions::login::<M>(&session, account_pk, false).await?;
+
+    let session_id = session.id().ok_or("Session has no id")?.to_string();
+    insert!(&AuthModule::<M>::global().db, M::OidcSession)
+        .return_nothing()
+        .single(&M::insertable_oidc_session(session_id, subject, sid))
+        .await?;
 
     Ok(Redirect::temporary("/"))
 }
+
+/// Logs out the sessions which logged in through a session of the OpenID Connect provider
+///
+/// The provider calls this endpoint when its session ends
+/// ([Back-Channel Logout](https://openid.net/specs/openid-connect-backchannel-1_0.html)).
+/// Register its url as the client's back-channel logout url at the provider.
+///
+/// The provider's requests don't carry an `Origin` header,
+/// so this endpoint has to be exempt from a CSRF protection like the one of `SessionSetup::cross_site`.
+///
+/// The body is a form (`application/x-www-form-urlencoded`) containing the `logout_token`.
+#[post("/logout/oidc/backchannel", core_crate = "::galvyn_core")]
+pub async fn oidc_backchannel_logout<M: AuthModels>(body: String) -> ApiResult<()> {
+    let logout_token = form_urlencoded::parse(body.as_bytes())
+        .find_map(|(key, value)| (key == "logout_token").then_some(value))
+        .ok_or_else(|| ApiError::client_error("Missing logout_token"))?;
+
+    let module = AuthModule::<M>::global();
+    let claims = module.oidc_logout.verify(&logout_token)?;
+
+    let sessions = match (&claims.sub, &claims.sid) {
+        (Some(sub), _) => {
+            QueryBuilder::new(
+                &module.db,
+                (M::oidc_session_session_id(), M::oidc_session_sid()),
+            )
+            .condition(M::oidc_session_subject().equals(sub))
+            .all()
+            .await?
+        }
+        (None, Some(sid)) => {
+            QueryBuilder::new(
+                &module.db,
+                (M::oidc_session_session_id(), M::oidc_session_sid()),
+            )
+            .condition(M::oidc_session_sid().equals(sid))
+            .all()
+            .await?
+        }
+        (None, None) => unreachable!("The verifier rejects logout tokens without sub and sid"),
+    };
+
+    for (session_id, session_sid) in sessions {
+        // A token with both claims only ends the provider's one session, not all of the account's
+        if claims.sid.as_ref().is_some_and(|sid| *sid != session_sid) {
+            continue;
+        }
+        sessions::end_session::<M>(&session_id).await?;
+    }
+    Ok(())
+}
@@ -1,19 +1,22 @@
+use galvyn_core::schema_generator::SchemaGenerator;
 use openidconnect::{AuthorizationCode, CsrfToken};
+use schemars::schema::Schema;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
-use std::borrow::Cow;
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GetLoginFlowsRequest {
     pub identifier: String,
 }
 
+/// The login flows available to an account
+///
+/// An account may have both OIDC and local credentials if they have been linked.
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-#[serde(tag = "provider")]
-pub enum GetLoginFlowsResponse {
-    Oidc(OidcLoginFlow),
-    Local(LocalLoginFlow),
+pub struct GetLoginFlowsResponse {
+    pub oidc: Option<OidcLoginFlow>,
+    pub local: Option<LocalLoginFlow>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -23,6 +26,8 @@ pub struct OidcLoginFlow {}
 pub struct LocalLoginFlow {
     pub password: bool,
     pub webauthn: bool,
+    /// Whether a TOTP code is required in addition to the password
+    pub totp: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
@@ -37,24 +42,236 @@ pub struct FinishLoginOidcRequest {
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LoginLocalWebauthnRequest {
     pub identifier: String,
+    /// Keep the session alive for the configured `remember_me_expiry` instead of the default expiry
+    #[serde(default)]
+    pub remember_me: bool,
+    /// A solved captcha, required if a captcha verifier is set and there have been too many failed logins
+    pub captcha: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LoginLocalPasswordRequest {
     pub identifier: String,
     pub password: String,
+    /// The current TOTP code, required if the account has enrolled a TOTP key
+    pub totp: Option<String>,
+    /// Keep the session alive for the configured `remember_me_expiry` instead of the default expiry
+    #[serde(default)]
+    pub remember_me: bool,
+    /// A solved captcha, required if a captcha verifier is set and there have been too many failed logins
+    pub captcha: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PublicKeyCredential(pub webauthn_rs::prelude::PublicKeyCredential);
-impl JsonSchema for PublicKeyCredential {
-    fn schema_name() -> String {
-        "PublicKeyCredential".to_owned()
-    }
-    fn schema_id() -> std::borrow::Cow<'static, str> {
-        Cow::Borrowed(concat!(module_path!(), "::", "PublicKeyCredential"))
-    }
-    fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
-        schemars::schema::Schema::Bool(true)
-    }
+/// The response of a successful login
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LoginResponse {
+    /// The access token, only issued if JWTs are configured instead of sessions
+    ///
+    /// Send it as `Authorization: Bearer <token>`.
+    /// Requests with a missing, invalid or expired token are rejected with `401 Unauthorized`.
+    pub access_token: Option<String>,
+    /// The number of seconds until the access token expires
+    pub expires_in: Option<u64>,
+    /// The token to obtain a new access token using `POST /token/refresh`
+    ///
+    /// It can only be used once and is replaced by the one in the refresh's response.
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DeactivateAccountRequest {
+    /// The logged-in account's identifier, to confirm the deactivation
+    pub identifier: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AdminGetAccountsRequest {
+    /// Only return accounts whose identifier contains this string
+    pub search: Option<String>,
+    /// The number of accounts to skip
+    pub offset: Option<u64>,
+    /// The maximum number of accounts to return (at most 100)
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AdminAccountInfo {
+    pub identifier: String,
+    /// The point in time (in seconds since the unix epoch) the account has been disabled
+    pub disabled_at: Option<i64>,
+    /// Whether the account can login through the OpenID Connect provider
+    pub oidc: bool,
+    /// Whether the account has local credentials
+    pub local: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AdminCreateAccountRequest {
+    pub identifier: String,
+    /// The account's initial password
+    ///
+    /// Without one, the account's owner has to reset the password before logging in.
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GetAuthEventsRequest {
+    /// The maximum number of events to return (at most 500)
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SessionInfo {
+    /// The identifier used to log out the session
+    pub id: String,
+    /// The point in time (in seconds since the unix epoch) the session has been logged in
+    pub created_at: i64,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LinkLocalAccountRequest {
+    /// The password to set
+    ///
+    /// It may be omitted to only login using passkeys.
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LoginLocalRecoveryCodeRequest {
+    pub identifier: String,
+    /// The account's password
+    ///
+    /// A recovery code only replaces the second factor,
+    /// accounts without a password can't login using one.
+    pub password: String,
+    /// One of the account's recovery codes
+    ///
+    /// Every code can only be used once.
+    pub code: String,
+    /// A solved captcha, required if a captcha verifier is set and there have been too many failed logins
+    pub captcha: Option<String>,
+}
+
+/// Recovery codes to login after losing the second factor or a passkey
+///
+/// They are only returned once and can't be retrieved later.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RecoveryCodesResponse {
+    pub codes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RegisterLocalWebauthnRequest {
+    /// A label for the user to recognize the key by
+    ///
+    /// It has to be unique per account and is shown by the authenticator as well.
+    pub label: String,
+    /// Whether the authenticator has to prove its make and model
+    ///
+    /// The attestation has to be signed by one of the configured certificate authorities.
+    /// An account's keys have to be either all attested or all not attested.
+    pub attested: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebauthnKeyInfo {
+    pub label: String,
+    pub attested: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RegisterLocalAccountRequest {
+    pub identifier: String,
+    pub password: String,
+    /// A solved captcha, required if a captcha verifier is set
+    pub captcha: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RequestPasswordResetRequest {
+    pub identifier: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FinishPasswordResetRequest {
+    /// The token delivered to the account's owner
+    pub token: String,
+    /// The new password
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RequestVerificationRequest {
+    pub identifier: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FinishVerificationRequest {
+    /// The token delivered to the account's owner
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateApiKeyRequest {
+    /// A label for the user to recognize the key by
+    pub label: String,
+    /// The scopes the key is restricted to
+    pub scopes: Vec<String>,
+    /// The number of seconds after which the key expires
+    ///
+    /// Keys without expiry stay valid until they are deleted.
+    pub expires_in: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CreateApiKeyResponse {
+    /// The key's identifier used to manage it
+    pub id: String,
+    /// The key to send in the `Authorization` header
+    ///
+    /// It is only returned once and can't be retrieved later.
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub label: String,
+    pub scopes: Vec<String>,
+    /// The point in time (in seconds since the unix epoch) after which the key is rejected
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EnrollLocalTotpRequest {
+    /// A label for the user to recognize the key by
+    ///
+    /// It is also shown as account name in authenticator apps.
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EnrollLocalTotpResponse {
+    /// The `otpauth://` URI to be encoded as QR code for authenticator apps
+    pub uri: String,
+    /// The base32 encoded secret for manual entry
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ConfirmLocalTotpRequest {
+    /// The current code generated from the enrolled secret
+    pub code: String,
+}
+
+/// Schema for webauthn's types which don't implement `JsonSchema`
+pub fn webauthn_schema(_gen: &mut SchemaGenerator) -> Schema {
+    Schema::Bool(true)
 }
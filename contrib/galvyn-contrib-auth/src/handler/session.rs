@@ -0,0 +1,72 @@
+use crate::handler::schema::SessionInfo;
+use crate::sessions;
+use crate::{AuthModels, AuthModule};
+use galvyn_core::re_exports::axum::extract::Path;
+use galvyn_core::re_exports::axum::Json;
+use galvyn_core::session::{RormStore, Session};
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::Module;
+use galvyn_macros::{delete, get};
+use rorm::crud::query::QueryBuilder;
+use rorm::internal::field::Field;
+use rorm::{and, FieldAccess, Model};
+
+#[get("/sessions", core_crate = "::galvyn_core")]
+pub async fn get_sessions<M: AuthModels>(session: Session) -> ApiResult<Json<Vec<SessionInfo>>> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+    let current = session.id().map(|id| id.to_string());
+
+    let db = &AuthModule::<M>::global().db;
+    let store = RormStore::new(db.clone());
+
+    let sessions = QueryBuilder::new(
+        db,
+        (
+            M::account_session_id(),
+            M::account_session_session_id(),
+            M::account_session_created_at(),
+        ),
+    )
+    .condition(M::account_session_fm().equals(&account_pk))
+    .all()
+    .await?;
+
+    let mut active = Vec::with_capacity(sessions.len());
+    for (id, session_id, created_at) in sessions {
+        if !store.is_active(&session_id).await? {
+            // Clean up sessions which expired
+            sessions::remove_from_index::<M>(&session_id).await?;
+            continue;
+        }
+        active.push(SessionInfo {
+            current: current.as_deref() == Some(session_id.as_str()),
+            id,
+            created_at,
+        });
+    }
+    Ok(Json(active))
+}
+
+#[delete("/sessions/{id}", core_crate = "::galvyn_core")]
+pub async fn delete_session<M: AuthModels>(
+    session: Session,
+    Path(id): Path<String>,
+) -> ApiResult<()> {
+    let account_pk: <<M::Account as Model>::Primary as Field>::Type =
+        session.get("account").await?.ok_or("Not logged-in")?;
+
+    let db = &AuthModule::<M>::global().db;
+
+    let (session_id,) = QueryBuilder::new(db, (M::account_session_session_id(),))
+        .condition(and![
+            M::account_session_fm().equals(&account_pk),
+            M::account_session_id().equals(&id),
+        ])
+        .optional()
+        .await?
+        .ok_or_else(|| ApiError::not_found("Session not found"))?;
+
+    sessions::end_session::<M>(&session_id).await?;
+    Ok(())
+}
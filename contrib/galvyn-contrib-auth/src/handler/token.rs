@@ -0,0 +1,84 @@
+use crate::handler::issue_tokens;
+use crate::handler::schema::{LoginResponse, RefreshTokenRequest};
+use crate::utils::{hash_token, unix_now};
+use crate::{AuthModels, AuthModule};
+use galvyn_core::re_exports::axum::Json;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::Module;
+use galvyn_macros::post;
+use rorm::crud::query::QueryBuilder;
+use rorm::prelude::ForeignModelByField;
+use rorm::FieldAccess;
+
+/// Exchanges a refresh token for a new access token and refresh token
+///
+/// The used refresh token is revoked.
+/// Unknown, revoked and expired refresh tokens are rejected with `401 Unauthorized`.
+/// Refresh tokens of disabled accounts are rejected with `403 Forbidden`.
+#[post("/token/refresh", core_crate = "::galvyn_core")]
+pub async fn refresh_token<M: AuthModels>(
+    Json(request): Json<RefreshTokenRequest>,
+) -> ApiResult<Json<LoginResponse>> {
+    if !AuthModule::<M>::global().uses_jwt() {
+        return Err(ApiError::not_found("JWTs are not configured").into());
+    }
+
+    let token_hash = hash_token(&request.refresh_token);
+
+    let mut tx = AuthModule::<M>::global().db.start_transaction().await?;
+
+    let (account, expires_at) = QueryBuilder::new(
+        &mut tx,
+        (M::refresh_token_fm(), M::refresh_token_expires_at()),
+    )
+    .condition(M::refresh_token_token_hash().equals(&token_hash))
+    .optional()
+    .await?
+    .ok_or_else(|| ApiError::unauthorized("Invalid refresh token"))?;
+
+    // Only the request which actually deleted the token may use it,
+    // concurrent requests with the same token see it already deleted.
+    let deleted = rorm::delete!(&mut tx, M::RefreshToken)
+        .condition(M::refresh_token_token_hash().equals(&token_hash))
+        .await?;
+    if deleted != 1 {
+        return Err(ApiError::unauthorized("Invalid refresh token").into());
+    }
+
+    let account_pk = match account {
+        ForeignModelByField::Key(x) => x,
+        ForeignModelByField::Instance(_) => unreachable!(),
+    };
+    let (disabled_at,) = QueryBuilder::new(&mut tx, (M::account_disabled_at(),))
+        .condition(M::account_pk().equals(&account_pk))
+        .optional()
+        .await?
+        .ok_or_else(|| ApiError::unauthorized("Invalid refresh token"))?;
+
+    tx.commit().await?;
+
+    if expires_at <= unix_now() {
+        return Err(ApiError::unauthorized("Expired refresh token").into());
+    }
+    if disabled_at.is_some() {
+        return Err(ApiError::forbidden("Account has been disabled").into());
+    }
+
+    let response = issue_tokens::<M>(&account_pk)
+        .await?
+        .ok_or("JWTs are not configured")?;
+    Ok(Json(response))
+}
+
+/// Revokes a refresh token
+///
+/// This should be called by clients when logging out.
+#[post("/token/revoke", core_crate = "::galvyn_core")]
+pub async fn revoke_refresh_token<M: AuthModels>(
+    Json(request): Json<RefreshTokenRequest>,
+) -> ApiResult<()> {
+    rorm::delete!(&AuthModule::<M>::global().db, M::RefreshToken)
+        .condition(M::refresh_token_token_hash().equals(&hash_token(&request.refresh_token)))
+        .await?;
+    Ok(())
+}
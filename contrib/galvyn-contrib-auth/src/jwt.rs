@@ -0,0 +1,178 @@
+//! Stateless authentication using signed JSON Web Tokens
+//!
+//! Deployments which can't use cookie sessions set `JWT_SECRET` in the [`AuthConfig`](crate::module::AuthConfig).
+//! The login handlers then respond with a short-lived access token instead of logging the session in.
+//! Clients send it in the `Authorization` header (`Authorization: Bearer <token>`),
+//! which is read by the [`JwtAuth`] extractor.
+//!
+//! Alongside the access token, a long-lived refresh token is issued.
+//! It is exchanged for a new pair of tokens using `POST /token/refresh`
+//! and can be revoked using `POST /token/revoke` or [`AuthModule::revoke_refresh_tokens`].
+//! Every refresh token can only be used once.
+//!
+//! The passkey login still keeps its challenge in the session between its two requests
+//! and the OpenID Connect login always uses the session.
+
+use crate::utils::{generate_token, hash_token, unix_now};
+use crate::{AuthModels, AuthModule};
+use galvyn_core::handler::request_part::{RequestPart, ShouldBeRequestPart};
+use galvyn_core::re_exports::axum::extract::FromRequestParts;
+use galvyn_core::re_exports::axum::http::header;
+use galvyn_core::re_exports::axum::http::request::Parts;
+use galvyn_core::stuff::api_error::ApiError;
+use galvyn_core::Module;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rorm::internal::field::Field;
+use rorm::{insert, FieldAccess, Model};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The keys and lifetime of the issued tokens
+#[derive(Clone)]
+pub(crate) struct JwtSettings {
+    pub(crate) encoding_key: EncodingKey,
+    pub(crate) decoding_key: DecodingKey,
+    pub(crate) expiry: Duration,
+    pub(crate) refresh_expiry: Duration,
+}
+
+impl JwtSettings {
+    /// Constructs the settings for tokens signed with HMAC-SHA256
+    pub(crate) fn from_secret(secret: &str, expiry: Duration, refresh_expiry: Duration) -> Self {
+        Self {
+            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
+            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
+            expiry,
+            refresh_expiry,
+        }
+    }
+}
+
+/// The claims of an issued access token
+#[derive(Serialize, Deserialize)]
+struct Claims<T> {
+    /// The primary key of the logged-in account
+    account: T,
+    /// Issued at (seconds since the unix epoch)
+    iat: i64,
+    /// Expiration time (seconds since the unix epoch)
+    exp: i64,
+}
+
+impl<M: AuthModels> AuthModule<M> {
+    /// Whether the login handlers issue JWTs instead of using the session
+    pub fn uses_jwt(&self) -> bool {
+        self.jwt.is_some()
+    }
+
+    /// Issues an access token for an account
+    ///
+    /// Returns `None` if no `JWT_SECRET` has been configured.
+    pub fn issue_jwt(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    ) -> Result<Option<String>, jsonwebtoken::errors::Error> {
+        let Some(jwt) = &self.jwt else {
+            return Ok(None);
+        };
+
+        let now = unix_now();
+        let claims = Claims {
+            account: account_pk,
+            iat: now,
+            exp: now + jwt.expiry.as_secs() as i64,
+        };
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &jwt.encoding_key).map(Some)
+    }
+
+    /// Issues a refresh token for an account
+    ///
+    /// Returns `None` if no `JWT_SECRET` has been configured.
+    pub(crate) async fn issue_refresh_token(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    ) -> Result<Option<String>, rorm::Error> {
+        let Some(jwt) = &self.jwt else {
+            return Ok(None);
+        };
+
+        let token = generate_token();
+        insert!(&self.db, M::RefreshToken)
+            .return_nothing()
+            .single(&M::insertable_refresh_token(
+                hash_token(&token),
+                unix_now() + jwt.refresh_expiry.as_secs() as i64,
+                account_pk,
+            ))
+            .await?;
+        Ok(Some(token))
+    }
+
+    /// Revokes all refresh tokens issued for an account
+    ///
+    /// Access tokens which have already been issued stay valid until they expire.
+    pub async fn revoke_refresh_tokens(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    ) -> Result<(), rorm::Error> {
+        rorm::delete!(&self.db, M::RefreshToken)
+            .condition(M::refresh_token_fm().equals(account_pk))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Extractor authenticating a request using an access token issued by the login handlers
+///
+/// Requests without a valid and unexpired token are rejected with `401 Unauthorized`,
+/// requests whose account has been disabled with `403 Forbidden`.
+pub struct JwtAuth<M: AuthModels> {
+    /// The primary key of the logged-in account
+    pub account_pk: <<M::Account as Model>::Primary as Field>::Type,
+}
+
+impl<S, M> FromRequestParts<S> for JwtAuth<M>
+where
+    S: Send + Sync,
+    M: AuthModels,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let jwt = AuthModule::<M>::global()
+            .jwt
+            .as_ref()
+            .ok_or_else(|| ApiError::server_error("JWT_SECRET has not been configured"))?;
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| ApiError::unauthorized("Missing access token"))?;
+
+        let claims =
+            jsonwebtoken::decode::<Claims<<<M::Account as Model>::Primary as Field>::Type>>(
+                token.trim(),
+                &jwt.decoding_key,
+                &Validation::new(Algorithm::HS256),
+            )
+            .map_err(ApiError::unauthorized)?
+            .claims;
+        let account_pk = claims.account;
+
+        // Disabling an account revokes its refresh tokens, but issued access tokens stay valid
+        let disabled = AuthModule::<M>::global()
+            .is_account_disabled(&account_pk)
+            .await
+            .map_err(ApiError::server_error)?;
+        if disabled {
+            return Err(ApiError::forbidden("Account has been disabled"));
+        }
+
+        Ok(Self { account_pk })
+    }
+}
+
+impl<M: AuthModels> ShouldBeRequestPart for JwtAuth<M> {}
+impl<M: AuthModels> RequestPart for JwtAuth<M> {}
@@ -1,7 +1,25 @@
+pub mod accounts;
+pub mod api_key;
+pub mod audit;
+#[cfg(feature = "oidc")]
+mod backchannel;
+pub mod captcha;
+#[cfg(feature = "oidc")]
+pub mod claims;
 pub mod handler;
+pub mod jwt;
 mod models;
 mod module;
+mod password;
+pub mod permissions;
+mod recovery;
+pub mod sessions;
+mod throttle;
+mod totp;
+mod utils;
 
 pub use models::AuthModels;
 pub use models::MaybeAttestedPasskey;
 pub use module::AuthModule;
+pub use module::AuthRouterBuilder;
+pub use module::TokenDelivery;
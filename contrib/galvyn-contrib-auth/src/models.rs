@@ -1,3 +1,4 @@
+use crate::audit::AuthEventKind;
 use rorm::fields::traits::FieldType;
 use rorm::fields::types::Json;
 use rorm::internal::field::as_db_type::AsDbType;
@@ -7,7 +8,7 @@ use rorm::prelude::ForeignModelByField;
 use rorm::{Model, Patch};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use webauthn_rs::prelude::{AttestedPasskey, Passkey};
+use webauthn_rs::prelude::{AttestedPasskey, CredentialID, Passkey};
 
 pub trait AuthModels: Send + Sync + 'static {
     type Account: Model<
@@ -16,6 +17,7 @@ pub trait AuthModels: Send + Sync + 'static {
                           + AsDbType
                           + Serialize
                           + DeserializeOwned
+                          + PartialEq
                           + Send
                           + Sync,
             >,
@@ -34,6 +36,15 @@ pub trait AuthModels: Send + Sync + 'static {
     fn account_pk() -> FieldProxy<<Self::Account as Model>::Primary, Self::Account> {
         FieldProxy::new()
     }
+    /// The point in time (in seconds since the unix epoch) the account has been disabled
+    ///
+    /// Disabled accounts can't login.
+    fn account_disabled_at(
+    ) -> FieldProxy<impl Field<Type = Option<i64>, Model = Self::Account>, Self::Account>;
+    /// Constructs a new account with the given identifier
+    ///
+    /// Any additional fields of the application's account model have to be set to defaults
+    /// (see [`accounts`](crate::accounts) for data which can't be defaulted).
     fn insertable_account(id: String) -> impl Patch<Model = Self::Account> + Send + Sync;
 
     type OidcAccount: Model<Primary: Field<Type: FieldType<Decoder: Send> + AsDbType + Send + Sync>>
@@ -74,6 +85,282 @@ pub trait AuthModels: Send + Sync + 'static {
     >;
     fn local_account_password(
     ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::LocalAccount>, Self::LocalAccount>;
+    /// Whether the account has been verified (for example its email address)
+    fn local_account_verified(
+    ) -> FieldProxy<impl Field<Type = bool, Model = Self::LocalAccount>, Self::LocalAccount>;
+    /// The SHA-256 hash of the pending token to verify the account with, hex encoded
+    ///
+    /// It should be unique.
+    fn local_account_verification_token_hash(
+    ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::LocalAccount>, Self::LocalAccount>;
+    /// The unix timestamp after which the pending verification token is no longer valid
+    fn local_account_verification_expires_at(
+    ) -> FieldProxy<impl Field<Type = Option<i64>, Model = Self::LocalAccount>, Self::LocalAccount>;
+    fn insertable_local_account(
+        password: Option<String>,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::LocalAccount> + Send + Sync;
+    /// The number of failed login attempts since the last successful one
+    fn local_account_failed_logins(
+    ) -> FieldProxy<impl Field<Type = i32, Model = Self::LocalAccount>, Self::LocalAccount>;
+    /// The point in time (in seconds since the unix epoch) until which logins are rejected
+    ///
+    /// It is set once the number of failed logins reaches the configured threshold.
+    fn local_account_locked_until(
+    ) -> FieldProxy<impl Field<Type = Option<i64>, Model = Self::LocalAccount>, Self::LocalAccount>;
+
+    type PasswordResetToken: Model + Send + Sync;
+    /// The foreign model field of `PasswordResetToken` pointing to `Account`
+    fn password_reset_token_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::PasswordResetToken,
+        >,
+        Self::PasswordResetToken,
+    >;
+    /// The SHA-256 hash of the secret token sent to the account's owner, hex encoded
+    ///
+    /// It should be unique.
+    fn password_reset_token_token_hash() -> FieldProxy<
+        impl Field<Type = String, Model = Self::PasswordResetToken>,
+        Self::PasswordResetToken,
+    >;
+    /// The point in time (in seconds since the unix epoch) after which the token is rejected
+    fn password_reset_token_expires_at() -> FieldProxy<
+        impl Field<Type = i64, Model = Self::PasswordResetToken>,
+        Self::PasswordResetToken,
+    >;
+    fn insertable_password_reset_token(
+        token_hash: String,
+        expires_at: i64,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::PasswordResetToken> + Send + Sync;
+
+    /// Failed logins counted per identifier and per client ip
+    type LoginThrottle: Model + Send + Sync;
+    /// The throttled key (for example `identifier:alice` or `ip:192.0.2.1`)
+    ///
+    /// It must be unique.
+    fn login_throttle_key(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::LoginThrottle>, Self::LoginThrottle>;
+    /// The number of failed logins in the current window
+    fn login_throttle_failures(
+    ) -> FieldProxy<impl Field<Type = i32, Model = Self::LoginThrottle>, Self::LoginThrottle>;
+    /// The point in time (in seconds since the unix epoch) at which the current window ends
+    fn login_throttle_window_end(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::LoginThrottle>, Self::LoginThrottle>;
+    fn insertable_login_throttle(
+        key: String,
+        failures: i32,
+        window_end: i64,
+    ) -> impl Patch<Model = Self::LoginThrottle> + Send + Sync;
+
+    /// A named set of permissions assigned to accounts
+    type Role: Model<Primary: Field<Type: FieldType<Decoder: Send> + AsDbType + Send + Sync>>
+        + Send
+        + Sync;
+    fn role_pk() -> FieldProxy<<Self::Role as Model>::Primary, Self::Role> {
+        FieldProxy::new()
+    }
+    /// The role's unique name
+    fn role_name() -> FieldProxy<impl Field<Type = String, Model = Self::Role>, Self::Role>;
+    fn insertable_role(name: String) -> impl Patch<Model = Self::Role> + Send + Sync;
+
+    /// A permission granted to a role
+    type RolePermission: Model + Send + Sync;
+    /// The foreign model field of `RolePermission` pointing to `Role`
+    ///
+    /// It should cascade on delete.
+    fn role_permission_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Role as Model>::Primary>,
+            Model = Self::RolePermission,
+        >,
+        Self::RolePermission,
+    >;
+    /// The permission's name
+    fn role_permission_name(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::RolePermission>, Self::RolePermission>;
+    fn insertable_role_permission(
+        name: String,
+        role_pk: &<<Self::Role as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::RolePermission> + Send + Sync;
+
+    /// The assignment of a role to an account
+    type AccountRole: Model + Send + Sync;
+    /// The foreign model field of `AccountRole` pointing to `Account`
+    fn account_role_account_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::AccountRole,
+        >,
+        Self::AccountRole,
+    >;
+    /// The foreign model field of `AccountRole` pointing to `Role`
+    ///
+    /// It should cascade on delete.
+    fn account_role_role_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Role as Model>::Primary>,
+            Model = Self::AccountRole,
+        >,
+        Self::AccountRole,
+    >;
+    fn insertable_account_role(
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+        role_pk: &<<Self::Role as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::AccountRole> + Send + Sync;
+
+    /// A key authenticating machine clients on behalf of an account
+    type ApiKey: Model + Send + Sync;
+    /// The foreign model field of `ApiKey` pointing to `Account`
+    ///
+    /// It should cascade on delete.
+    fn api_key_fm() -> FieldProxy<
+        impl Field<Type = ForeignModelByField<<Self::Account as Model>::Primary>, Model = Self::ApiKey>,
+        Self::ApiKey,
+    >;
+    /// The key's public and unique identifier
+    fn api_key_id() -> FieldProxy<impl Field<Type = String, Model = Self::ApiKey>, Self::ApiKey>;
+    /// A label for the user to recognize the key by
+    fn api_key_label() -> FieldProxy<impl Field<Type = String, Model = Self::ApiKey>, Self::ApiKey>;
+    /// The SHA-256 hash of the key's secret, hex encoded
+    ///
+    /// It should be unique.
+    fn api_key_secret_hash(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::ApiKey>, Self::ApiKey>;
+    /// The scopes the key is restricted to
+    fn api_key_scopes(
+    ) -> FieldProxy<impl Field<Type = Json<Vec<String>>, Model = Self::ApiKey>, Self::ApiKey>;
+    /// The point in time (in seconds since the unix epoch) after which the key is rejected
+    fn api_key_expires_at(
+    ) -> FieldProxy<impl Field<Type = Option<i64>, Model = Self::ApiKey>, Self::ApiKey>;
+    fn insertable_api_key(
+        id: String,
+        label: String,
+        secret_hash: String,
+        scopes: Vec<String>,
+        expires_at: Option<i64>,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::ApiKey> + Send + Sync;
+
+    /// The index of the sessions an account is logged in with
+    type AccountSession: Model + Send + Sync;
+    /// The foreign model field of `AccountSession` pointing to `Account`
+    ///
+    /// It should cascade on delete.
+    fn account_session_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::AccountSession,
+        >,
+        Self::AccountSession,
+    >;
+    /// The public and unique identifier used to manage the session
+    fn account_session_id(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::AccountSession>, Self::AccountSession>;
+    /// The id of the `GalvynSession`
+    ///
+    /// It should be unique.
+    /// It is the value of the session's cookie and must never be returned to users.
+    fn account_session_session_id(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::AccountSession>, Self::AccountSession>;
+    /// The point in time (in seconds since the unix epoch) the account logged in
+    fn account_session_created_at(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::AccountSession>, Self::AccountSession>;
+    fn insertable_account_session(
+        id: String,
+        session_id: String,
+        created_at: i64,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::AccountSession> + Send + Sync;
+
+    /// The OpenID Connect provider's session a `GalvynSession` logged in through
+    ///
+    /// It is used to end the `GalvynSession` when the provider notifies about a logout.
+    type OidcSession: Model + Send + Sync;
+    /// The id of the `GalvynSession`
+    ///
+    /// It should be unique.
+    fn oidc_session_session_id(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::OidcSession>, Self::OidcSession>;
+    /// The provider's identifier of the account (the `sub` claim)
+    fn oidc_session_subject(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::OidcSession>, Self::OidcSession>;
+    /// The provider's identifier of its session (the `sid` claim)
+    ///
+    /// It is empty if the provider doesn't issue one.
+    fn oidc_session_sid(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::OidcSession>, Self::OidcSession>;
+    fn insertable_oidc_session(
+        session_id: String,
+        subject: String,
+        sid: String,
+    ) -> impl Patch<Model = Self::OidcSession> + Send + Sync;
+
+    /// A revocable token to obtain new JWTs without logging in again
+    type RefreshToken: Model + Send + Sync;
+    /// The foreign model field of `RefreshToken` pointing to `Account`
+    ///
+    /// It should cascade on delete.
+    fn refresh_token_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::RefreshToken,
+        >,
+        Self::RefreshToken,
+    >;
+    /// The SHA-256 hash of the token, hex encoded
+    ///
+    /// It should be unique.
+    fn refresh_token_token_hash(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::RefreshToken>, Self::RefreshToken>;
+    /// The point in time (in seconds since the unix epoch) after which the token is rejected
+    fn refresh_token_expires_at(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::RefreshToken>, Self::RefreshToken>;
+    fn insertable_refresh_token(
+        token_hash: String,
+        expires_at: i64,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::RefreshToken> + Send + Sync;
+
+    /// An entry of the audit log
+    type AuthEvent: Model + Send + Sync;
+    /// The foreign model field of `AuthEvent` pointing to `Account`
+    ///
+    /// It should cascade on delete.
+    fn auth_event_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::AuthEvent,
+        >,
+        Self::AuthEvent,
+    >;
+    fn auth_event_kind(
+    ) -> FieldProxy<impl Field<Type = AuthEventKind, Model = Self::AuthEvent>, Self::AuthEvent>;
+    /// The address of the client which caused the event
+    fn auth_event_ip(
+    ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::AuthEvent>, Self::AuthEvent>;
+    /// The user agent of the client which caused the event
+    ///
+    /// It is truncated to 255 characters.
+    fn auth_event_user_agent(
+    ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::AuthEvent>, Self::AuthEvent>;
+    /// The point in time (in seconds since the unix epoch) the event occurred
+    fn auth_event_created_at(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::AuthEvent>, Self::AuthEvent>;
+    /// The identifier of the account which caused the event on behalf of the event's account
+    /// (i.e. the admin impersonating it)
+    fn auth_event_actor(
+    ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::AuthEvent>, Self::AuthEvent>;
+    fn insertable_auth_event(
+        kind: AuthEventKind,
+        ip: Option<String>,
+        user_agent: Option<String>,
+        actor: Option<String>,
+        created_at: i64,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::AuthEvent> + Send + Sync;
 
     type TotpKey: Model;
     fn totp_key_pk() -> FieldProxy<<Self::TotpKey as Model>::Primary, Self::TotpKey> {
@@ -86,6 +373,40 @@ pub trait AuthModels: Send + Sync + 'static {
         >,
         Self::TotpKey,
     >;
+    /// The key's shared secret
+    fn totp_key_secret(
+    ) -> FieldProxy<impl Field<Type = Vec<u8>, Model = Self::TotpKey>, Self::TotpKey>;
+    /// The time step of the last code accepted for this key
+    ///
+    /// Codes of this or an earlier step are rejected, so an observed code can't be replayed.
+    fn totp_key_last_used_step(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::TotpKey>, Self::TotpKey>;
+    fn insertable_totp_key(
+        label: String,
+        secret: Vec<u8>,
+        last_used_step: i64,
+        local_account_pk: &<<Self::LocalAccount as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::TotpKey> + Send + Sync;
+
+    /// A one-time code to login without the second factor
+    type RecoveryCode: Model + Send + Sync;
+    /// The foreign model field of `RecoveryCode` pointing to `LocalAccount`
+    ///
+    /// It should cascade on delete.
+    fn recovery_code_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::LocalAccount as Model>::Primary>,
+            Model = Self::RecoveryCode,
+        >,
+        Self::RecoveryCode,
+    >;
+    /// The SHA-256 hash of the code, hex encoded
+    fn recovery_code_code_hash(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::RecoveryCode>, Self::RecoveryCode>;
+    fn insertable_recovery_code(
+        code_hash: String,
+        local_account_pk: &<<Self::LocalAccount as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::RecoveryCode> + Send + Sync;
 
     type WebauthnKey: Model;
     fn webauthn_key_pk() -> FieldProxy<<Self::WebauthnKey as Model>::Primary, Self::WebauthnKey> {
@@ -98,10 +419,20 @@ pub trait AuthModels: Send + Sync + 'static {
         >,
         Self::WebauthnKey,
     >;
+    /// A label for the user to recognize the key by
+    ///
+    /// It is unique per local account.
+    fn webauthn_key_label(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::WebauthnKey>, Self::WebauthnKey>;
     fn webauthn_key_key() -> FieldProxy<
         impl Field<Type = Json<MaybeAttestedPasskey>, Model = Self::WebauthnKey>,
         Self::WebauthnKey,
     >;
+    fn insertable_webauthn_key(
+        label: String,
+        key: MaybeAttestedPasskey,
+        local_account_pk: &<<Self::LocalAccount as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::WebauthnKey> + Send + Sync;
 }
 
 #[derive(Serialize, Deserialize)]
@@ -110,3 +441,13 @@ pub enum MaybeAttestedPasskey {
     NotAttested(Passkey),
     Attested(AttestedPasskey),
 }
+
+impl MaybeAttestedPasskey {
+    /// The id of the credential, regardless of whether it is attested
+    pub fn cred_id(&self) -> &CredentialID {
+        match self {
+            MaybeAttestedPasskey::NotAttested(key) => key.cred_id(),
+            MaybeAttestedPasskey::Attested(key) => key.cred_id(),
+        }
+    }
+}
@@ -1,21 +1,37 @@
+use crate::accounts::AccountHook;
+use crate::captcha::CaptchaVerifier;
+#[cfg(feature = "oidc")]
+use crate::claims::ClaimsMapper;
+use crate::jwt::JwtSettings;
+use crate::throttle::RateLimit;
 use crate::{handler, AuthModels};
+use galvyn_core::stuff::api_error::ApiResult;
 use galvyn_core::{GalvynRouter, InitError, Module, PreInitError};
 #[cfg(feature = "oidc")]
 use openidconnect::core::{CoreClient as OidcClient, CoreProviderMetadata};
 #[cfg(feature = "oidc")]
 use openidconnect::reqwest::async_http_client;
 use openidconnect::{ClientId, ClientSecret, IssuerUrl};
-use rorm::Database;
+use rorm::crud::update::UpdateBuilder;
+use rorm::internal::field::Field;
+use rorm::{Database, FieldAccess, Model};
 use serde::{Deserialize, Serialize};
 use std::future::{ready, Future};
 use std::marker::PhantomData;
 use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::OnceLock;
+use std::time::Duration;
 use std::{fs, io};
 use webauthn_rs::prelude::{AttestationCaList, Url};
 use webauthn_rs::{Webauthn, WebauthnBuilder};
 
 #[cfg(not(feature = "oidc"))]
 type OidcClient = ();
+#[cfg(feature = "oidc")]
+use crate::backchannel::LogoutTokenVerifier as OidcLogout;
+#[cfg(not(feature = "oidc"))]
+type OidcLogout = ();
 
 /// The authentication module provides the state required by the authentication handlers
 pub struct AuthModule<M: AuthModels> {
@@ -23,32 +39,84 @@ pub struct AuthModule<M: AuthModels> {
     pub(crate) db: Database,
     #[cfg_attr(not(feature = "oidc"), allow(unused))]
     pub(crate) oidc: OidcClient,
+    #[cfg_attr(not(feature = "oidc"), allow(unused))]
+    pub(crate) oidc_logout: OidcLogout,
     pub(crate) webauthn: Webauthn,
     pub(crate) attestation_ca_list: AttestationCaList,
+    pub(crate) local: LocalSettings,
+    pub(crate) jwt: Option<JwtSettings>,
+    pub(crate) admin_role: String,
+    pub(crate) password_reset_delivery: OnceLock<TokenDelivery>,
+    pub(crate) verification_delivery: OnceLock<TokenDelivery>,
+    pub(crate) captcha_verifier: OnceLock<Box<dyn CaptchaVerifier>>,
+    pub(crate) account_hook: OnceLock<Box<dyn AccountHook<M>>>,
+    #[cfg(feature = "oidc")]
+    pub(crate) claims_mapper: OnceLock<Box<dyn ClaimsMapper<M>>>,
     models: PhantomData<M>,
 }
 
 #[non_exhaustive]
 pub struct AuthHandler<M: AuthModels> {
     pub get_login_flow: handler::get_login_flow<M>,
-    pub logout: handler::logout,
+    pub logout: handler::logout<M>,
 
     #[cfg(feature = "oidc")]
     pub login_oidc: handler::login_oidc<M>,
     #[cfg(feature = "oidc")]
     pub finish_login_oidc: handler::finish_login_oidc<M>,
+    #[cfg(feature = "oidc")]
+    pub link_oidc: handler::link_oidc<M>,
+    #[cfg(feature = "oidc")]
+    pub oidc_backchannel_logout: handler::oidc_backchannel_logout<M>,
     #[cfg(not(feature = "oidc"))]
     #[allow(unused)]
     login_oidc: (),
     #[cfg(not(feature = "oidc"))]
     #[allow(unused)]
     finish_login_oidc: (),
+    #[cfg(not(feature = "oidc"))]
+    #[allow(unused)]
+    link_oidc: (),
+    #[cfg(not(feature = "oidc"))]
+    #[allow(unused)]
+    oidc_backchannel_logout: (),
 
     pub login_local_webauthn: handler::login_local_webauthn<M>,
     pub finish_login_local_webauthn: handler::finish_login_local_webauthn<M>,
+    pub register_local_webauthn: handler::register_local_webauthn<M>,
+    pub finish_register_local_webauthn: handler::finish_register_local_webauthn<M>,
+    pub get_local_webauthn_keys: handler::get_local_webauthn_keys<M>,
+    pub delete_local_webauthn_key: handler::delete_local_webauthn_key<M>,
     pub login_local_password: handler::login_local_password<M>,
+    pub login_local_recovery_code: handler::login_local_recovery_code<M>,
+    pub regenerate_local_recovery_codes: handler::regenerate_local_recovery_codes<M>,
     pub set_local_password: handler::set_local_password<M>,
     pub delete_local_password: handler::delete_local_password<M>,
+    pub enroll_local_totp: handler::enroll_local_totp<M>,
+    pub confirm_local_totp: handler::confirm_local_totp<M>,
+    pub register_local_account: handler::register_local_account<M>,
+    pub link_local_account: handler::link_local_account<M>,
+    pub request_password_reset: handler::request_password_reset<M>,
+    pub finish_password_reset: handler::finish_password_reset<M>,
+    pub request_verification: handler::request_verification<M>,
+    pub finish_verification: handler::finish_verification<M>,
+    pub create_api_key: handler::create_api_key<M>,
+    pub get_api_keys: handler::get_api_keys<M>,
+    pub delete_api_key: handler::delete_api_key<M>,
+    pub admin_get_accounts: handler::admin_get_accounts<M>,
+    pub admin_create_account: handler::admin_create_account<M>,
+    pub admin_disable_account: handler::admin_disable_account<M>,
+    pub admin_enable_account: handler::admin_enable_account<M>,
+    pub admin_reset_password: handler::admin_reset_password<M>,
+    pub admin_get_auth_events: handler::admin_get_auth_events<M>,
+    pub admin_impersonate: handler::admin_impersonate<M>,
+    pub admin_end_impersonation: handler::admin_end_impersonation<M>,
+    pub get_auth_events: handler::get_auth_events<M>,
+    pub deactivate_account: handler::deactivate_account<M>,
+    pub get_sessions: handler::get_sessions<M>,
+    pub delete_session: handler::delete_session<M>,
+    pub refresh_token: handler::refresh_token<M>,
+    pub revoke_refresh_token: handler::revoke_refresh_token<M>,
 }
 
 impl<M: AuthModels> Clone for AuthHandler<M> {
@@ -67,47 +135,537 @@ pub struct AuthConfig {
     pub webauthn_id: String,
     pub webauthn_origin: Url,
     pub webauthn_attestation_ca_list: PathBuf,
+
+    /// Whether users may create local accounts themselves using `POST /register`
+    #[serde(default)]
+    pub registration: bool,
+
+    /// The issuer shown in authenticator apps for TOTP keys
+    ///
+    /// Defaults to `webauthn_id`.
+    #[serde(default)]
+    pub totp_issuer: Option<String>,
+
+    /// The number of consecutive failed logins after which a local account is locked
+    ///
+    /// `0` disables the lockout.
+    #[serde(default = "default_lockout_threshold")]
+    pub lockout_threshold: i32,
+    /// The number of seconds a local account stays locked
+    #[serde(default = "default_lockout_duration")]
+    pub lockout_duration: u64,
+
+    /// Whether local accounts have to be verified before they can login
+    #[serde(default)]
+    pub require_verification: bool,
+
+    /// The number of failed logins per identifier and per client ip after which logins are rejected
+    ///
+    /// `0` disables the rate limit.
+    #[serde(default = "default_rate_limit_attempts")]
+    pub rate_limit_attempts: i32,
+    /// The number of seconds failed logins are counted for the rate limit
+    #[serde(default = "default_rate_limit_window")]
+    pub rate_limit_window: u64,
+
+    /// The number of failed logins per identifier or per client ip after which a captcha is required
+    ///
+    /// `0` always requires one.
+    /// Captchas are only required once a [`CaptchaVerifier`] has been set.
+    #[serde(default = "default_captcha_after_failures")]
+    pub captcha_after_failures: i32,
+
+    /// The number of seconds a password reset token stays valid
+    #[serde(default = "default_password_reset_expiry")]
+    pub password_reset_expiry: u64,
+    /// The number of seconds a verification token stays valid
+    #[serde(default = "default_verification_expiry")]
+    pub verification_expiry: u64,
+
+    /// The number of seconds of inactivity after which a session expires if it logged in with `remember_me`
+    ///
+    /// Other sessions use the expiry configured in the `SessionSetup`.
+    #[serde(default = "default_remember_me_expiry")]
+    pub remember_me_expiry: u64,
+
+    /// The secret used to sign JWTs
+    ///
+    /// If set, the login handlers respond with an access token instead of using the session.
+    /// See [`jwt`](crate::jwt) for details.
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+    /// The number of seconds an issued JWT stays valid
+    #[serde(default = "default_jwt_expiry")]
+    pub jwt_expiry: u64,
+    /// The number of seconds an issued refresh token stays valid
+    #[serde(default = "default_jwt_refresh_expiry")]
+    pub jwt_refresh_expiry: u64,
+
+    /// The role required to use the admin endpoints
+    #[serde(default = "default_admin_role")]
+    pub admin_role: String,
+}
+
+fn default_lockout_threshold() -> i32 {
+    5
+}
+
+fn default_lockout_duration() -> u64 {
+    15 * 60
+}
+
+fn default_rate_limit_attempts() -> i32 {
+    10
+}
+
+fn default_rate_limit_window() -> u64 {
+    15 * 60
+}
+
+fn default_captcha_after_failures() -> i32 {
+    3
+}
+
+fn default_password_reset_expiry() -> u64 {
+    60 * 60
+}
+
+fn default_verification_expiry() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_jwt_expiry() -> u64 {
+    15 * 60
+}
+
+fn default_remember_me_expiry() -> u64 {
+    30 * 24 * 60 * 60
+}
+
+fn default_jwt_refresh_expiry() -> u64 {
+    30 * 24 * 60 * 60
+}
+
+fn default_admin_role() -> String {
+    "admin".to_string()
+}
+
+/// The settings of the local login flows taken from the [`AuthConfig`]
+#[derive(Clone, Debug)]
+pub(crate) struct LocalSettings {
+    pub(crate) lockout: Lockout,
+    pub(crate) rate_limit: RateLimit,
+    pub(crate) totp_issuer: String,
+    pub(crate) registration: bool,
+    pub(crate) require_verification: bool,
+    pub(crate) password_reset_expiry: Duration,
+    pub(crate) verification_expiry: Duration,
+    pub(crate) remember_me_expiry: Duration,
+    pub(crate) captcha_after_failures: i32,
+}
+
+/// The thresholds for locking local accounts after repeated failed logins
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Lockout {
+    /// The number of consecutive failed logins after which an account is locked
+    pub(crate) threshold: i32,
+    /// The time an account stays locked
+    pub(crate) duration: Duration,
+}
+
+/// Callback delivering a token to an account's owner (for example by email)
+///
+/// It receives the account's identifier and the token.
+pub type TokenDelivery = Box<
+    dyn Fn(String, String) -> Pin<Box<dyn Future<Output = ApiResult<()>> + Send>> + Send + Sync,
+>;
+
+impl<M: AuthModels> AuthModule<M> {
+    /// Sets the callback delivering password reset tokens
+    ///
+    /// Without it, requesting a password reset fails.
+    /// This can only be set once, returns the rejected callback if it has already been set.
+    pub fn set_password_reset_delivery<F, Fut>(&self, delivery: F) -> Result<(), TokenDelivery>
+    where
+        F: Fn(String, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ApiResult<()>> + Send + 'static,
+    {
+        self.password_reset_delivery
+            .set(Box::new(move |identifier, token| {
+                Box::pin(delivery(identifier, token))
+            }))
+    }
+
+    /// Sets the callback delivering tokens to verify local accounts
+    ///
+    /// Without it, requesting a verification fails.
+    /// This can only be set once, returns the rejected callback if it has already been set.
+    pub fn set_verification_delivery<F, Fut>(&self, delivery: F) -> Result<(), TokenDelivery>
+    where
+        F: Fn(String, String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ApiResult<()>> + Send + 'static,
+    {
+        self.verification_delivery
+            .set(Box::new(move |identifier, token| {
+                Box::pin(delivery(identifier, token))
+            }))
+    }
+
+    /// Unlocks a local account and resets its failed login counter
+    ///
+    /// This is intended for administrators to lift a lockout before it expires on its own.
+    pub async fn unlock_local_account(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    ) -> Result<(), rorm::Error> {
+        UpdateBuilder::new(&self.db)
+            .condition(M::local_account_fm().equals(account_pk))
+            .set(M::local_account_failed_logins(), 0)
+            .set(M::local_account_locked_until(), None)
+            .exec()
+            .await?;
+        Ok(())
+    }
 }
 
 impl<M: AuthModels> AuthHandler<M> {
+    /// Creates a router containing all endpoints except the admin ones
+    ///
+    /// Use [`AuthHandler::router_builder`] to only include the flows your application uses
+    /// or to include the admin endpoints.
     pub fn as_router(&self) -> GalvynRouter {
-        let router = GalvynRouter::new()
-            .handler(self.get_login_flow)
-            .handler(self.logout)
-            .handler(self.login_local_webauthn)
-            .handler(self.finish_login_local_webauthn)
-            .handler(self.login_local_password)
-            .handler(self.set_local_password)
-            .handler(self.delete_local_password);
+        let builder = self
+            .router_builder()
+            .with_password_login()
+            .with_totp()
+            .with_recovery_codes()
+            .with_registration()
+            .with_password_reset()
+            .with_verification()
+            .with_api_keys()
+            .with_sessions()
+            .with_auth_events()
+            .with_account_deactivation()
+            .with_refresh_tokens()
+            .with_account_linking()
+            .with_webauthn();
+
+        #[cfg(feature = "oidc")]
+        let builder = builder.with_oidc();
+
+        builder.build()
+    }
+
+    /// Creates a builder to compose a router containing only the selected login flows
+    ///
+    /// The endpoints to get the login flow and to logout are always included.
+    ///
+    /// ```rust,ignore
+    /// let router = AuthModule::<MyModels>::global()
+    ///     .handler
+    ///     .router_builder()
+    ///     .with_password_login()
+    ///     .build();
+    /// ```
+    pub fn router_builder(&self) -> AuthRouterBuilder<M> {
+        AuthRouterBuilder {
+            handler: *self,
+            password_login: false,
+            totp: false,
+            recovery_codes: false,
+            registration: false,
+            password_reset: false,
+            verification: false,
+            api_keys: false,
+            sessions: false,
+            auth_events: false,
+            account_deactivation: false,
+            refresh_tokens: false,
+            account_linking: false,
+            admin: false,
+            webauthn: false,
+            #[cfg(feature = "oidc")]
+            oidc: false,
+        }
+    }
+}
+
+/// Builder for a [`GalvynRouter`] containing a selection of the authentication endpoints
+///
+/// Created by [`AuthHandler::router_builder`]
+pub struct AuthRouterBuilder<M: AuthModels> {
+    handler: AuthHandler<M>,
+    password_login: bool,
+    totp: bool,
+    recovery_codes: bool,
+    registration: bool,
+    password_reset: bool,
+    verification: bool,
+    api_keys: bool,
+    sessions: bool,
+    auth_events: bool,
+    account_deactivation: bool,
+    refresh_tokens: bool,
+    account_linking: bool,
+    admin: bool,
+    webauthn: bool,
+    #[cfg(feature = "oidc")]
+    oidc: bool,
+}
+
+impl<M: AuthModels> AuthRouterBuilder<M> {
+    /// Includes the endpoints to login with, set and delete a local account's password
+    pub fn with_password_login(mut self) -> Self {
+        self.password_login = true;
+        self
+    }
+
+    /// Includes the endpoints to enroll a TOTP key as second factor for the password login
+    pub fn with_totp(mut self) -> Self {
+        self.totp = true;
+        self
+    }
+
+    /// Includes the endpoints to login with and regenerate recovery codes
+    ///
+    /// Recovery codes are returned when a local account enrolls its first TOTP key or passkey.
+    pub fn with_recovery_codes(mut self) -> Self {
+        self.recovery_codes = true;
+        self
+    }
+
+    /// Includes the endpoint to register a new local account
+    ///
+    /// The endpoint rejects all requests unless registration is enabled in the [`AuthConfig`].
+    pub fn with_registration(mut self) -> Self {
+        self.registration = true;
+        self
+    }
+
+    /// Includes the endpoints to reset a local account's forgotten password
+    ///
+    /// The tokens are delivered through the callback set with [`AuthModule::set_password_reset_delivery`].
+    pub fn with_password_reset(mut self) -> Self {
+        self.password_reset = true;
+        self
+    }
+
+    /// Includes the endpoints to verify a local account (for example its email address)
+    ///
+    /// The tokens are delivered through the callback set with [`AuthModule::set_verification_delivery`].
+    pub fn with_verification(mut self) -> Self {
+        self.verification = true;
+        self
+    }
+
+    /// Includes the endpoints for logged-in users to manage their api keys
+    ///
+    /// See [`ApiKeyAuth`](crate::api_key::ApiKeyAuth) to authenticate requests using them.
+    pub fn with_api_keys(mut self) -> Self {
+        self.api_keys = true;
+        self
+    }
+
+    /// Includes the endpoints for logged-in users to list their sessions and log them out remotely
+    pub fn with_sessions(mut self) -> Self {
+        self.sessions = true;
+        self
+    }
+
+    /// Includes the endpoint for logged-in users to list their recent logins and other [`audit`](crate::audit) events
+    pub fn with_auth_events(mut self) -> Self {
+        self.auth_events = true;
+        self
+    }
+
+    /// Includes the endpoint for logged-in users to deactivate their account
+    pub fn with_account_deactivation(mut self) -> Self {
+        self.account_deactivation = true;
+        self
+    }
+
+    /// Includes the endpoints to refresh and revoke the tokens issued if JWTs are configured
+    ///
+    /// See [`jwt`](crate::jwt) for details.
+    pub fn with_refresh_tokens(mut self) -> Self {
+        self.refresh_tokens = true;
+        self
+    }
+
+    /// Includes the endpoints for logged-in users to add local credentials or an OIDC account
+    ///
+    /// Linking an OIDC account requires the OIDC endpoints to be included as well.
+    pub fn with_account_linking(mut self) -> Self {
+        self.account_linking = true;
+        self
+    }
+
+    /// Includes the endpoints for administrators to manage accounts
+    ///
+    /// They can only be used by accounts with the role configured as `ADMIN_ROLE` in the [`AuthConfig`],
+    /// except for the one ending an impersonation.
+    pub fn with_admin(mut self) -> Self {
+        self.admin = true;
+        self
+    }
+
+    /// Includes the endpoints to login with and manage a local account's passkeys
+    pub fn with_webauthn(mut self) -> Self {
+        self.webauthn = true;
+        self
+    }
+
+    /// Includes the endpoints to login through the configured OpenID Connect provider
+    #[cfg(feature = "oidc")]
+    pub fn with_oidc(mut self) -> Self {
+        self.oidc = true;
+        self
+    }
+
+    /// Builds the router containing the selected endpoints
+    pub fn build(self) -> GalvynRouter {
+        let handler = self.handler;
+        let mut router = GalvynRouter::new()
+            .handler(handler.get_login_flow)
+            .handler(handler.logout);
+
+        if self.password_login {
+            router = router
+                .handler(handler.login_local_password)
+                .handler(handler.set_local_password)
+                .handler(handler.delete_local_password);
+        }
+
+        if self.totp {
+            router = router
+                .handler(handler.enroll_local_totp)
+                .handler(handler.confirm_local_totp);
+        }
+
+        if self.recovery_codes {
+            router = router
+                .handler(handler.login_local_recovery_code)
+                .handler(handler.regenerate_local_recovery_codes);
+        }
+
+        if self.registration {
+            router = router.handler(handler.register_local_account);
+        }
+
+        if self.password_reset {
+            router = router
+                .handler(handler.request_password_reset)
+                .handler(handler.finish_password_reset);
+        }
+
+        if self.verification {
+            router = router
+                .handler(handler.request_verification)
+                .handler(handler.finish_verification);
+        }
+
+        if self.api_keys {
+            router = router
+                .handler(handler.create_api_key)
+                .handler(handler.get_api_keys)
+                .handler(handler.delete_api_key);
+        }
+
+        if self.sessions {
+            router = router
+                .handler(handler.get_sessions)
+                .handler(handler.delete_session);
+        }
+
+        if self.auth_events {
+            router = router.handler(handler.get_auth_events);
+        }
+
+        if self.account_deactivation {
+            router = router.handler(handler.deactivate_account);
+        }
+
+        if self.refresh_tokens {
+            router = router
+                .handler(handler.refresh_token)
+                .handler(handler.revoke_refresh_token);
+        }
+
+        if self.account_linking {
+            router = router.handler(handler.link_local_account);
+
+            #[cfg(feature = "oidc")]
+            {
+                router = router.handler(handler.link_oidc);
+            }
+        }
+
+        if self.admin {
+            router = router
+                .handler(handler.admin_get_accounts)
+                .handler(handler.admin_create_account)
+                .handler(handler.admin_disable_account)
+                .handler(handler.admin_enable_account)
+                .handler(handler.admin_reset_password)
+                .handler(handler.admin_get_auth_events)
+                .handler(handler.admin_impersonate)
+                .handler(handler.admin_end_impersonation);
+        }
+
+        if self.webauthn {
+            router = router
+                .handler(handler.login_local_webauthn)
+                .handler(handler.finish_login_local_webauthn)
+                .handler(handler.register_local_webauthn)
+                .handler(handler.finish_register_local_webauthn)
+                .handler(handler.get_local_webauthn_keys)
+                .handler(handler.delete_local_webauthn_key);
+        }
 
         #[cfg(feature = "oidc")]
-        let router = router
-            .handler(self.login_oidc)
-            .handler(self.finish_login_oidc);
+        if self.oidc {
+            router = router
+                .handler(handler.login_oidc)
+                .handler(handler.finish_login_oidc)
+                .handler(handler.oidc_backchannel_logout);
+        }
 
         router
     }
 }
 
 impl<M: AuthModels> Module for AuthModule<M> {
-    type PreInit = (OidcClient, Webauthn, AttestationCaList);
+    type PreInit = (
+        OidcClient,
+        OidcLogout,
+        Webauthn,
+        AttestationCaList,
+        LocalSettings,
+        Option<JwtSettings>,
+        String,
+    );
 
     fn pre_init() -> impl Future<Output = Result<Self::PreInit, PreInitError>> + Send {
         async move {
             let auth_config: AuthConfig = envy::from_env()?;
 
             #[cfg(not(feature = "oidc"))]
-            let oidc = ();
+            let (oidc, oidc_logout) = ((), ());
             #[cfg(feature = "oidc")]
-            let oidc = OidcClient::from_provider_metadata(
-                CoreProviderMetadata::discover_async(
+            let (oidc, oidc_logout) = {
+                let metadata = CoreProviderMetadata::discover_async(
                     auth_config.oidc_issuer_url,
                     async_http_client,
                 )
-                .await?,
-                auth_config.oidc_client_id,
-                Some(auth_config.oidc_client_secret),
-            );
+                .await?;
+                let oidc_logout = OidcLogout::new(&metadata, &auth_config.oidc_client_id)?;
+                let oidc = OidcClient::from_provider_metadata(
+                    metadata,
+                    auth_config.oidc_client_id,
+                    Some(auth_config.oidc_client_secret),
+                );
+                (oidc, oidc_logout)
+            };
             // TODO: can't set redirect uri before application author mounted our handler to its router :(
 
             let webauthn =
@@ -117,21 +675,67 @@ impl<M: AuthModels> Module for AuthModule<M> {
                 &auth_config.webauthn_attestation_ca_list,
             )?))?;
 
-            Ok((oidc, webauthn, attestation_ca_list))
+            let local = LocalSettings {
+                lockout: Lockout {
+                    threshold: auth_config.lockout_threshold,
+                    duration: Duration::from_secs(auth_config.lockout_duration),
+                },
+                rate_limit: RateLimit {
+                    attempts: auth_config.rate_limit_attempts,
+                    window: Duration::from_secs(auth_config.rate_limit_window),
+                },
+                totp_issuer: auth_config
+                    .totp_issuer
+                    .unwrap_or_else(|| auth_config.webauthn_id.clone()),
+                registration: auth_config.registration,
+                require_verification: auth_config.require_verification,
+                password_reset_expiry: Duration::from_secs(auth_config.password_reset_expiry),
+                verification_expiry: Duration::from_secs(auth_config.verification_expiry),
+                remember_me_expiry: Duration::from_secs(auth_config.remember_me_expiry),
+                captcha_after_failures: auth_config.captcha_after_failures,
+            };
+
+            let jwt = auth_config.jwt_secret.as_deref().map(|secret| {
+                JwtSettings::from_secret(
+                    secret,
+                    Duration::from_secs(auth_config.jwt_expiry),
+                    Duration::from_secs(auth_config.jwt_refresh_expiry),
+                )
+            });
+
+            Ok((
+                oidc,
+                oidc_logout,
+                webauthn,
+                attestation_ca_list,
+                local,
+                jwt,
+                auth_config.admin_role,
+            ))
         }
     }
 
     type Dependencies = (Database,);
 
     fn init(
-        (oidc, webauthn, attestation_ca_list): Self::PreInit,
+        (oidc, oidc_logout, webauthn, attestation_ca_list, local, jwt, admin_role): Self::PreInit,
         (db,): &mut Self::Dependencies,
     ) -> impl Future<Output = Result<Self, InitError>> + Send {
         ready(Ok(Self {
             db: db.clone(),
             oidc,
+            oidc_logout,
             webauthn,
             attestation_ca_list,
+            local,
+            jwt,
+            admin_role,
+            password_reset_delivery: OnceLock::new(),
+            verification_delivery: OnceLock::new(),
+            captcha_verifier: OnceLock::new(),
+            account_hook: OnceLock::new(),
+            #[cfg(feature = "oidc")]
+            claims_mapper: OnceLock::new(),
             models: PhantomData,
             handler: AuthHandler {
                 get_login_flow: Default::default(),
@@ -139,12 +743,45 @@ impl<M: AuthModels> Module for AuthModule<M> {
 
                 login_oidc: Default::default(),
                 finish_login_oidc: Default::default(),
+                link_oidc: Default::default(),
+                oidc_backchannel_logout: Default::default(),
 
                 login_local_webauthn: Default::default(),
                 finish_login_local_webauthn: Default::default(),
+                register_local_webauthn: Default::default(),
+                finish_register_local_webauthn: Default::default(),
+                get_local_webauthn_keys: Default::default(),
+                delete_local_webauthn_key: Default::default(),
                 login_local_password: Default::default(),
+                login_local_recovery_code: Default::default(),
+                regenerate_local_recovery_codes: Default::default(),
                 set_local_password: Default::default(),
                 delete_local_password: Default::default(),
+                enroll_local_totp: Default::default(),
+                confirm_local_totp: Default::default(),
+                register_local_account: Default::default(),
+                link_local_account: Default::default(),
+                request_password_reset: Default::default(),
+                finish_password_reset: Default::default(),
+                request_verification: Default::default(),
+                finish_verification: Default::default(),
+                create_api_key: Default::default(),
+                get_api_keys: Default::default(),
+                delete_api_key: Default::default(),
+                admin_get_accounts: Default::default(),
+                admin_create_account: Default::default(),
+                admin_disable_account: Default::default(),
+                admin_enable_account: Default::default(),
+                admin_reset_password: Default::default(),
+                admin_get_auth_events: Default::default(),
+                admin_impersonate: Default::default(),
+                admin_end_impersonation: Default::default(),
+                get_auth_events: Default::default(),
+                deactivate_account: Default::default(),
+                get_sessions: Default::default(),
+                delete_session: Default::default(),
+                refresh_token: Default::default(),
+                revoke_refresh_token: Default::default(),
             },
         }))
     }
@@ -0,0 +1,57 @@
+//! Hashing and verification of local accounts' passwords
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{self, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+
+/// The outcome of [`verify_password`]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Verification {
+    /// The password doesn't match the hash
+    Invalid,
+
+    /// The password matches the hash
+    Valid,
+
+    /// The password matches the hash, but the hash uses outdated parameters
+    ///
+    /// The password should be rehashed using [`hash_password`] and stored again.
+    Outdated,
+}
+
+/// Hashes a password using argon2id with a random salt
+///
+/// Returns the hash as PHC string which contains the salt and the parameters used.
+pub(crate) fn hash_password(password: &str) -> Result<String, password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default()
+        .hash_password(password.as_bytes(), &salt)?
+        .to_string())
+}
+
+/// Verifies a password against a PHC string produced by [`hash_password`]
+pub(crate) fn verify_password(
+    password: &str,
+    hash: &str,
+) -> Result<Verification, password_hash::Error> {
+    let hash = PasswordHash::new(hash)?;
+    match Argon2::default().verify_password(password.as_bytes(), &hash) {
+        Ok(()) => {}
+        Err(password_hash::Error::Password) => return Ok(Verification::Invalid),
+        Err(error) => return Err(error),
+    }
+
+    let current = Params::default();
+    let is_current = hash.algorithm == Algorithm::Argon2id.ident()
+        && hash.version == Some(Version::V0x13.into())
+        && Params::try_from(&hash).is_ok_and(|params| {
+            params.m_cost() == current.m_cost()
+                && params.t_cost() == current.t_cost()
+                && params.p_cost() == current.p_cost()
+        });
+    Ok(if is_current {
+        Verification::Valid
+    } else {
+        Verification::Outdated
+    })
+}
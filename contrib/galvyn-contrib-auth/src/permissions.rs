@@ -0,0 +1,287 @@
+//! Roles and permissions of accounts
+//!
+//! Permissions are plain names (for example `"posts.delete"`) granted to roles,
+//! which in turn are assigned to accounts.
+//! Handlers declare the permission they require using the [`RequirePermission`] extractor:
+//!
+//! ```rust,ignore
+//! struct DeletePosts;
+//! impl Permission for DeletePosts {
+//!     const NAME: &'static str = "posts.delete";
+//! }
+//!
+//! #[delete("/posts/{uuid}")]
+//! async fn delete_post(
+//!     _: RequirePermission<MyModels, DeletePosts>,
+//!     Path(uuid): Path<Uuid>,
+//! ) -> ApiResult<()> {
+//!     // ...
+//! }
+//! ```
+
+use crate::{AuthModels, AuthModule};
+use galvyn_core::handler::request_part::{RequestPart, ShouldBeRequestPart};
+use galvyn_core::re_exports::axum::extract::FromRequestParts;
+use galvyn_core::re_exports::axum::http::request::Parts;
+use galvyn_core::session::Session;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::Module;
+use rorm::crud::query::QueryBuilder;
+use rorm::internal::field::Field;
+use rorm::prelude::ForeignModelByField;
+use rorm::{and, insert, FieldAccess, Model};
+use std::marker::PhantomData;
+
+/// A permission which can be required by handlers using [`RequirePermission`]
+pub trait Permission: Send + Sync + 'static {
+    /// The permission's name as granted to roles
+    const NAME: &'static str;
+}
+
+/// Extractor rejecting requests whose account lacks the permission `P`
+///
+/// Requests without a logged-in account are rejected with `401 Unauthorized`,
+/// requests whose account lacks the permission or has been disabled with `403 Forbidden`.
+pub struct RequirePermission<M: AuthModels, P: Permission> {
+    /// The logged-in account's primary key
+    pub account_pk: <<M::Account as Model>::Primary as Field>::Type,
+    permission: PhantomData<P>,
+}
+
+impl<S, M, P> FromRequestParts<S> for RequirePermission<M, P>
+where
+    S: Send + Sync,
+    M: AuthModels,
+    P: Permission,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let SessionAccount { account_pk } =
+            SessionAccount::<M>::from_request_parts(parts, state).await?;
+
+        let granted = AuthModule::<M>::global()
+            .has_permission(&account_pk, P::NAME)
+            .await
+            .map_err(ApiError::server_error)?;
+        if !granted {
+            return Err(ApiError::forbidden(format!(
+                "Missing permission: {}",
+                P::NAME
+            )));
+        }
+
+        Ok(Self {
+            account_pk,
+            permission: PhantomData,
+        })
+    }
+}
+
+impl<M: AuthModels, P: Permission> ShouldBeRequestPart for RequirePermission<M, P> {}
+impl<M: AuthModels, P: Permission> RequestPart for RequirePermission<M, P> {}
+
+impl<M: AuthModels> AuthModule<M> {
+    /// Checks whether any of an account's roles grants a permission
+    pub async fn has_permission(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+        permission: &str,
+    ) -> Result<bool, rorm::Error> {
+        let mut tx = self.db.start_transaction().await?;
+
+        let roles = QueryBuilder::new(&mut tx, (M::account_role_role_fm(),))
+            .condition(M::account_role_account_fm().equals(account_pk))
+            .all()
+            .await?;
+
+        let mut granted = false;
+        for (role,) in roles {
+            let role_pk = match role {
+                ForeignModelByField::Key(x) => x,
+                ForeignModelByField::Instance(_) => unreachable!(),
+            };
+            granted = QueryBuilder::new(&mut tx, (M::role_permission_name(),))
+                .condition(and![
+                    M::role_permission_fm().equals(&role_pk),
+                    M::role_permission_name().equals(permission),
+                ])
+                .optional()
+                .await?
+                .is_some();
+            if granted {
+                break;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(granted)
+    }
+
+    /// Checks whether a role has been assigned to an account
+    pub async fn has_role(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+        role: &str,
+    ) -> Result<bool, rorm::Error> {
+        let mut tx = self.db.start_transaction().await?;
+
+        let Some((role_pk,)) = QueryBuilder::new(&mut tx, (M::role_pk(),))
+            .condition(M::role_name().equals(role))
+            .optional()
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        let assigned = QueryBuilder::new(&mut tx, (M::account_role_role_fm(),))
+            .condition(and![
+                M::account_role_account_fm().equals(account_pk),
+                M::account_role_role_fm().equals(&role_pk),
+            ])
+            .optional()
+            .await?
+            .is_some();
+
+        tx.commit().await?;
+        Ok(assigned)
+    }
+
+    /// Creates a new role without any permissions
+    pub async fn create_role(&self, name: &str) -> ApiResult<()> {
+        let mut tx = self.db.start_transaction().await?;
+
+        let existing = QueryBuilder::new(&mut tx, (M::role_pk(),))
+            .condition(M::role_name().equals(name))
+            .optional()
+            .await?;
+        if existing.is_some() {
+            return Err(ApiError::client_error("Role already exists").into());
+        }
+
+        insert!(&mut tx, M::Role)
+            .return_nothing()
+            .single(&M::insertable_role(name.to_string()))
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Deletes a role, revoking it from all accounts
+    pub async fn delete_role(&self, name: &str) -> ApiResult<()> {
+        rorm::delete!(&self.db, M::Role)
+            .condition(M::role_name().equals(name))
+            .await?;
+        Ok(())
+    }
+
+    /// Grants a permission to a role
+    pub async fn grant_permission(&self, role: &str, permission: &str) -> ApiResult<()> {
+        let mut tx = self.db.start_transaction().await?;
+
+        let (role_pk,) = QueryBuilder::new(&mut tx, (M::role_pk(),))
+            .condition(M::role_name().equals(role))
+            .optional()
+            .await?
+            .ok_or_else(|| ApiError::not_found("Role not found"))?;
+
+        let existing = QueryBuilder::new(&mut tx, (M::role_permission_name(),))
+            .condition(and![
+                M::role_permission_fm().equals(&role_pk),
+                M::role_permission_name().equals(permission),
+            ])
+            .optional()
+            .await?;
+        if existing.is_none() {
+            insert!(&mut tx, M::RolePermission)
+                .return_nothing()
+                .single(&M::insertable_role_permission(
+                    permission.to_string(),
+                    &role_pk,
+                ))
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Revokes a permission from a role
+    pub async fn revoke_permission(&self, role: &str, permission: &str) -> ApiResult<()> {
+        let mut tx = self.db.start_transaction().await?;
+
+        let (role_pk,) = QueryBuilder::new(&mut tx, (M::role_pk(),))
+            .condition(M::role_name().equals(role))
+            .optional()
+            .await?
+            .ok_or_else(|| ApiError::not_found("Role not found"))?;
+
+        rorm::delete!(&mut tx, M::RolePermission)
+            .condition(and![
+                M::role_permission_fm().equals(&role_pk),
+                M::role_permission_name().equals(permission),
+            ])
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Assigns a role to an account
+    pub async fn assign_role(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+        role: &str,
+    ) -> ApiResult<()> {
+        let mut tx = self.db.start_transaction().await?;
+
+        let (role_pk,) = QueryBuilder::new(&mut tx, (M::role_pk(),))
+            .condition(M::role_name().equals(role))
+            .optional()
+            .await?
+            .ok_or_else(|| ApiError::not_found("Role not found"))?;
+
+        let existing = QueryBuilder::new(&mut tx, (M::account_role_role_fm(),))
+            .condition(and![
+                M::account_role_account_fm().equals(account_pk),
+                M::account_role_role_fm().equals(&role_pk),
+            ])
+            .optional()
+            .await?;
+        if existing.is_none() {
+            insert!(&mut tx, M::AccountRole)
+                .return_nothing()
+                .single(&M::insertable_account_role(account_pk, &role_pk))
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Removes a role from an account
+    pub async fn unassign_role(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+        role: &str,
+    ) -> ApiResult<()> {
+        let mut tx = self.db.start_transaction().await?;
+
+        let (role_pk,) = QueryBuilder::new(&mut tx, (M::role_pk(),))
+            .condition(M::role_name().equals(role))
+            .optional()
+            .await?
+            .ok_or_else(|| ApiError::not_found("Role not found"))?;
+
+        rorm::delete!(&mut tx, M::AccountRole)
+            .condition(and![
+                M::account_role_account_fm().equals(account_pk),
+                M::account_role_role_fm().equals(&role_pk),
+            ])
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+//! One-time recovery codes to login after losing the second factor or a passkey
+
+use crate::utils::hash_token;
+use crate::AuthModels;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use rorm::crud::query::QueryBuilder;
+use rorm::db::Transaction;
+use rorm::internal::field::Field;
+use rorm::{and, insert, FieldAccess, Model};
+
+/// The number of codes generated at once
+const CODES: usize = 10;
+
+/// Replaces a local account's recovery codes with new ones
+///
+/// Returns the codes to show to the user once, only their hashes are stored.
+pub(crate) async fn generate<M: AuthModels>(
+    tx: &mut Transaction,
+    local_account_pk: &<<M::LocalAccount as Model>::Primary as Field>::Type,
+) -> Result<Vec<String>, rorm::Error> {
+    rorm::delete!(&mut *tx, M::RecoveryCode)
+        .condition(M::recovery_code_fm().equals(local_account_pk))
+        .await?;
+
+    let codes = (0..CODES).map(|_| generate_code()).collect::<Vec<_>>();
+    for code in &codes {
+        insert!(&mut *tx, M::RecoveryCode)
+            .return_nothing()
+            .single(&M::insertable_recovery_code(
+                hash_token(&normalize(code)),
+                local_account_pk,
+            ))
+            .await?;
+    }
+    Ok(codes)
+}
+
+/// Generates recovery codes unless the local account already has some
+pub(crate) async fn generate_if_missing<M: AuthModels>(
+    tx: &mut Transaction,
+    local_account_pk: &<<M::LocalAccount as Model>::Primary as Field>::Type,
+) -> Result<Option<Vec<String>>, rorm::Error> {
+    let existing = QueryBuilder::new(&mut *tx, (M::recovery_code_code_hash(),))
+        .condition(M::recovery_code_fm().equals(local_account_pk))
+        .optional()
+        .await?;
+    if existing.is_some() {
+        return Ok(None);
+    }
+    generate::<M>(tx, local_account_pk).await.map(Some)
+}
+
+/// Uses up a recovery code
+///
+/// Returns whether the code was valid.
+pub(crate) async fn consume<M: AuthModels>(
+    tx: &mut Transaction,
+    local_account_pk: &<<M::LocalAccount as Model>::Primary as Field>::Type,
+    code: &str,
+) -> Result<bool, rorm::Error> {
+    let deleted = rorm::delete!(&mut *tx, M::RecoveryCode)
+        .condition(and![
+            M::recovery_code_fm().equals(local_account_pk),
+            M::recovery_code_code_hash().equals(&hash_token(&normalize(code))),
+        ])
+        .await?;
+    Ok(deleted > 0)
+}
+
+/// Generates a code with 80 bits of entropy formatted as four groups of five hex characters
+fn generate_code() -> String {
+    let mut bytes = [0; 10];
+    OsRng.fill_bytes(&mut bytes);
+    let hex = bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+    [&hex[0..5], &hex[5..10], &hex[10..15], &hex[15..20]].join("-")
+}
+
+/// Removes the formatting users might have changed while copying the code
+fn normalize(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
@@ -0,0 +1,265 @@
+//! Sessions of logged-in accounts
+//!
+//! Handlers read the account logged in through the session using the [`SessionAccount`] extractor.
+//! Sensitive operations should use [`RecentAuth`] instead, which requires a recent login.
+//!
+//! The sessions themselves are stored as `GalvynSession`s, which can't be queried by their account.
+//! Therefore, every login through a session adds an `AccountSession` pointing to it.
+
+use crate::utils::{generate_token, unix_now};
+use crate::{AuthModels, AuthModule};
+use galvyn_core::handler::request_part::{RequestPart, ShouldBeRequestPart};
+use galvyn_core::re_exports::axum::extract::FromRequestParts;
+use galvyn_core::re_exports::axum::http::request::Parts;
+use galvyn_core::re_exports::axum::http::{header, HeaderValue};
+use galvyn_core::re_exports::tower_sessions::cookie::time;
+use galvyn_core::re_exports::tower_sessions::Expiry;
+use galvyn_core::session::{RormStore, Session};
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::Module;
+use rorm::crud::query::QueryBuilder;
+use rorm::crud::update::UpdateBuilder;
+use rorm::internal::field::Field;
+use rorm::{insert, FieldAccess, Model};
+
+/// Extractor for the account logged in through the session
+///
+/// Requests without a logged-in account are rejected with `401 Unauthorized`,
+/// requests whose account has been disabled with `403 Forbidden`.
+pub struct SessionAccount<M: AuthModels> {
+    /// The primary key of the logged-in account
+    pub account_pk: <<M::Account as Model>::Primary as Field>::Type,
+}
+
+impl<S, M> FromRequestParts<S> for SessionAccount<M>
+where
+    S: Send + Sync,
+    M: AuthModels,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let session = Session::from_request_parts(parts, state)
+            .await
+            .map_err(|(_, error)| ApiError::server_error(error))?;
+        let account_pk: <<M::Account as Model>::Primary as Field>::Type = session
+            .get("account")
+            .await
+            .map_err(ApiError::server_error)?
+            .ok_or_else(|| ApiError::unauthorized("Not logged-in"))?;
+
+        // Disabling an account revokes its sessions, this guards against races with a login
+        let disabled = AuthModule::<M>::global()
+            .is_account_disabled(&account_pk)
+            .await
+            .map_err(ApiError::server_error)?;
+        if disabled {
+            return Err(ApiError::forbidden("Account has been disabled"));
+        }
+
+        Ok(Self { account_pk })
+    }
+}
+
+impl<M: AuthModels> ShouldBeRequestPart for SessionAccount<M> {}
+impl<M: AuthModels> RequestPart for SessionAccount<M> {}
+
+/// Extractor for the account logged in through the session, if it logged in within the last `SECONDS`
+///
+/// Use it for sensitive operations like changing the email or deleting the account,
+/// so a session left open on a shared device can't be used to take over the account.
+///
+/// Requests are rejected like by [`SessionAccount`].
+/// Additionally, requests whose session logged in too long ago are rejected with `401 Unauthorized`
+/// and the challenge `WWW-Authenticate: Session error="insufficient_user_authentication", max_age="<SECONDS>"`
+/// (following [RFC 9470](https://www.rfc-editor.org/rfc/rfc9470)).
+/// Clients should ask the user to login again and retry the request afterward.
+pub struct RecentAuth<M: AuthModels, const SECONDS: u64> {
+    /// The primary key of the logged-in account
+    pub account_pk: <<M::Account as Model>::Primary as Field>::Type,
+}
+
+impl<S, M, const SECONDS: u64> FromRequestParts<S> for RecentAuth<M, SECONDS>
+where
+    S: Send + Sync,
+    M: AuthModels,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let SessionAccount { account_pk } =
+            SessionAccount::<M>::from_request_parts(parts, state).await?;
+
+        let session = Session::from_request_parts(parts, state)
+            .await
+            .map_err(|(_, error)| ApiError::server_error(error))?;
+        let authenticated_at: Option<i64> = session
+            .get(AUTHENTICATED_AT)
+            .await
+            .map_err(ApiError::server_error)?;
+
+        let recent = authenticated_at
+            .is_some_and(|authenticated_at| unix_now() - authenticated_at <= SECONDS as i64);
+        if !recent {
+            let challenge =
+                format!(r#"Session error="insufficient_user_authentication", max_age="{SECONDS}""#);
+            return Err(
+                ApiError::unauthorized("Re-authentication required").with_header(
+                    header::WWW_AUTHENTICATE,
+                    HeaderValue::try_from(challenge).map_err(ApiError::server_error)?,
+                ),
+            );
+        }
+
+        Ok(Self { account_pk })
+    }
+}
+
+impl<M: AuthModels, const SECONDS: u64> ShouldBeRequestPart for RecentAuth<M, SECONDS> {}
+impl<M: AuthModels, const SECONDS: u64> RequestPart for RecentAuth<M, SECONDS> {}
+
+/// The session key storing the point in time (in seconds since the unix epoch) the account logged in
+const AUTHENTICATED_AT: &str = "authenticated_at";
+
+/// Stores the logged-in account in the session and adds it to the index
+///
+/// If `remember_me` is set, the session's expiry is extended to the configured `remember_me_expiry`.
+pub(crate) async fn login<M: AuthModels>(
+    session: &Session,
+    account_pk: <<M::Account as Model>::Primary as Field>::Type,
+    remember_me: bool,
+) -> ApiResult<()> {
+    if remember_me {
+        let expiry = AuthModule::<M>::global().local.remember_me_expiry;
+        session.set_expiry(Some(Expiry::OnInactivity(time::Duration::seconds(
+            expiry.as_secs() as i64,
+        ))));
+    }
+    session.insert("account", &account_pk).await?;
+    session.insert(AUTHENTICATED_AT, unix_now()).await?;
+
+    // New sessions don't have an id until they are saved
+    session.save().await?;
+    let session_id = session.id().ok_or("Session has no id")?.to_string();
+
+    insert!(&AuthModule::<M>::global().db, M::AccountSession)
+        .return_nothing()
+        .single(&M::insertable_account_session(
+            generate_token(),
+            session_id,
+            unix_now(),
+            &account_pk,
+        ))
+        .await?;
+    Ok(())
+}
+
+/// Removes the logged-in account from the session and the index
+pub(crate) async fn logout<M: AuthModels>(session: &Session) -> ApiResult<()> {
+    session.remove::<serde::de::IgnoredAny>("account").await?;
+    session
+        .remove::<serde::de::IgnoredAny>(AUTHENTICATED_AT)
+        .await?;
+    session
+        .remove::<serde::de::IgnoredAny>(IMPERSONATOR)
+        .await?;
+
+    if let Some(session_id) = session.id() {
+        remove_from_index::<M>(&session_id.to_string()).await?;
+    }
+    Ok(())
+}
+
+/// The session key storing the admin's account while it impersonates another one
+///
+/// The session's index entry keeps pointing to the admin's account,
+/// so revoking the admin's sessions ends the impersonation as well.
+pub(crate) const IMPERSONATOR: &str = "impersonator";
+
+/// Gives the session a new id and moves its index entries to the new id
+///
+/// This should be done whenever the account acting through the session changes,
+/// so an id leaked before can't be used to act as the new account.
+pub(crate) async fn cycle_id<M: AuthModels>(session: &Session) -> ApiResult<()> {
+    let old_id = session.id().map(|id| id.to_string());
+    session.cycle_id().await?;
+
+    // The new id is only generated when the session is saved
+    session.save().await?;
+    let (Some(old_id), Some(new_id)) = (old_id, session.id()) else {
+        return Ok(());
+    };
+    let new_id = new_id.to_string();
+
+    let db = &AuthModule::<M>::global().db;
+    UpdateBuilder::new(db)
+        .condition(M::account_session_session_id().equals(&old_id))
+        .set(M::account_session_session_id(), new_id.clone())
+        .exec()
+        .await?;
+    UpdateBuilder::new(db)
+        .condition(M::oidc_session_session_id().equals(&old_id))
+        .set(M::oidc_session_session_id(), new_id)
+        .exec()
+        .await?;
+    Ok(())
+}
+
+/// Gets the admin's account if the session is impersonating another account
+pub(crate) async fn impersonator<M: AuthModels>(
+    session: &Session,
+) -> ApiResult<Option<<<M::Account as Model>::Primary as Field>::Type>> {
+    Ok(session.get(IMPERSONATOR).await?)
+}
+
+impl<M: AuthModels> AuthModule<M> {
+    /// Logs an account out of all its sessions
+    ///
+    /// JWTs which have already been issued stay valid until they expire.
+    pub async fn revoke_sessions(
+        &self,
+        account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    ) -> Result<(), rorm::Error> {
+        revoke_sessions_except::<M>(account_pk, None).await
+    }
+}
+
+/// Logs an account out of all its sessions except the one with the given id
+pub(crate) async fn revoke_sessions_except<M: AuthModels>(
+    account_pk: &<<M::Account as Model>::Primary as Field>::Type,
+    except: Option<&str>,
+) -> Result<(), rorm::Error> {
+    let sessions = QueryBuilder::new(
+        &AuthModule::<M>::global().db,
+        (M::account_session_session_id(),),
+    )
+    .condition(M::account_session_fm().equals(account_pk))
+    .all()
+    .await?;
+    for (session_id,) in sessions {
+        if Some(session_id.as_str()) == except {
+            continue;
+        }
+        end_session::<M>(&session_id).await?;
+    }
+    Ok(())
+}
+
+/// Deletes a `GalvynSession` and removes it from the index
+pub(crate) async fn end_session<M: AuthModels>(session_id: &str) -> Result<(), rorm::Error> {
+    let db = &AuthModule::<M>::global().db;
+    RormStore::new(db.clone()).delete_by_id(session_id).await?;
+    remove_from_index::<M>(session_id).await
+}
+
+/// Removes a `GalvynSession` from the index after it has been deleted or logged out
+pub(crate) async fn remove_from_index<M: AuthModels>(session_id: &str) -> Result<(), rorm::Error> {
+    let db = &AuthModule::<M>::global().db;
+    rorm::delete!(db, M::AccountSession)
+        .condition(M::account_session_session_id().equals(session_id))
+        .await?;
+    rorm::delete!(db, M::OidcSession)
+        .condition(M::oidc_session_session_id().equals(session_id))
+        .await?;
+    Ok(())
+}
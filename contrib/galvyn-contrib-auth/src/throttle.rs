@@ -0,0 +1,189 @@
+//! Rate limiting of failed logins per identifier and per client ip
+
+use crate::utils::unix_now;
+use crate::{AuthModels, AuthModule};
+use galvyn_core::re_exports::axum::http::header;
+use galvyn_core::re_exports::axum::http::HeaderValue;
+use galvyn_core::stuff::api_error::{ApiError, ApiResult};
+use galvyn_core::stuff::client_ip::ClientIp;
+use galvyn_core::Module;
+use rorm::crud::query::QueryBuilder;
+use rorm::crud::update::UpdateBuilder;
+use rorm::db::Transaction;
+use rorm::{and, insert, FieldAccess};
+use std::time::Duration;
+
+/// The number of failed logins allowed per window
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct RateLimit {
+    /// The number of failed logins after which further logins are rejected
+    ///
+    /// `0` disables the rate limit.
+    pub(crate) attempts: i32,
+    /// The duration of a window
+    pub(crate) window: Duration,
+}
+
+/// The keys a login attempt is throttled by
+pub(crate) struct ThrottleKeys {
+    identifier: String,
+    ip: String,
+}
+
+impl ThrottleKeys {
+    pub(crate) fn new(identifier: &str, ClientIp(ip): ClientIp) -> Self {
+        Self {
+            identifier: format!("identifier:{identifier}"),
+            ip: format!("ip:{ip}"),
+        }
+    }
+
+    fn iter(&self) -> [&String; 2] {
+        [&self.identifier, &self.ip]
+    }
+}
+
+/// Rejects the login with `429 Too Many Requests` if any key has exceeded the rate limit
+pub(crate) async fn check<M: AuthModels>(
+    tx: &mut Transaction,
+    keys: &ThrottleKeys,
+) -> ApiResult<()> {
+    let rate_limit = AuthModule::<M>::global().local.rate_limit;
+    if rate_limit.attempts == 0 {
+        return Ok(());
+    }
+
+    let now = unix_now();
+    for key in keys.iter() {
+        let Some((failures, window_end)) = QueryBuilder::new(
+            &mut *tx,
+            (M::login_throttle_failures(), M::login_throttle_window_end()),
+        )
+        .condition(M::login_throttle_key().equals(key))
+        .optional()
+        .await?
+        else {
+            continue;
+        };
+
+        if window_end > now && failures >= rate_limit.attempts {
+            return Err(ApiError::too_many_requests("Too many failed logins")
+                .with_header(header::RETRY_AFTER, HeaderValue::from(window_end - now))
+                .into());
+        }
+    }
+    Ok(())
+}
+
+/// The highest number of failed logins of any key in the current window
+pub(crate) async fn failures<M: AuthModels>(
+    tx: &mut Transaction,
+    keys: &ThrottleKeys,
+) -> ApiResult<i32> {
+    let now = unix_now();
+    let mut max = 0;
+    for key in keys.iter() {
+        let throttle = QueryBuilder::new(
+            &mut *tx,
+            (M::login_throttle_failures(), M::login_throttle_window_end()),
+        )
+        .condition(M::login_throttle_key().equals(key))
+        .optional()
+        .await?;
+        if let Some((failures, window_end)) = throttle {
+            if window_end > now {
+                max = max.max(failures);
+            }
+        }
+    }
+    Ok(max)
+}
+
+/// Counts a failed login for every key
+///
+/// The failures are counted even if the rate limit is disabled,
+/// as long as they are needed to decide whether to require a captcha.
+///
+/// This runs outside the login's transaction, so a concurrent failure inserting the same key first
+/// doesn't abort it. A row is only written if it hasn't changed since it has been read,
+/// otherwise it is read again, so concurrent failures can't overwrite each other's increment.
+pub(crate) async fn record_failure<M: AuthModels>(keys: &ThrottleKeys) -> ApiResult<()> {
+    let module = AuthModule::<M>::global();
+    let rate_limit = module.local.rate_limit;
+    if rate_limit.attempts == 0 && module.captcha_verifier.get().is_none() {
+        return Ok(());
+    }
+
+    for key in keys.iter() {
+        loop {
+            let now = unix_now();
+            let existing = QueryBuilder::new(
+                &module.db,
+                (M::login_throttle_failures(), M::login_throttle_window_end()),
+            )
+            .condition(M::login_throttle_key().equals(key))
+            .optional()
+            .await?;
+
+            let Some((old_failures, old_window_end)) = existing else {
+                let inserted = insert!(&module.db, M::LoginThrottle)
+                    .return_nothing()
+                    .single(&M::insertable_login_throttle(
+                        key.clone(),
+                        1,
+                        now + rate_limit.window.as_secs() as i64,
+                    ))
+                    .await;
+                match inserted {
+                    Ok(()) => break,
+                    Err(error) => {
+                        // Another failure has inserted the key in the meantime
+                        let exists = QueryBuilder::new(&module.db, (M::login_throttle_failures(),))
+                            .condition(M::login_throttle_key().equals(key))
+                            .optional()
+                            .await?
+                            .is_some();
+                        if exists {
+                            continue;
+                        }
+                        return Err(error.into());
+                    }
+                }
+            };
+
+            let (failures, window_end) = if old_window_end > now {
+                (old_failures + 1, old_window_end)
+            } else {
+                (1, now + rate_limit.window.as_secs() as i64)
+            };
+
+            let updated = UpdateBuilder::new(&module.db)
+                .condition(and![
+                    M::login_throttle_key().equals(key),
+                    M::login_throttle_failures().equals(old_failures),
+                    M::login_throttle_window_end().equals(old_window_end),
+                ])
+                .set(M::login_throttle_failures(), failures)
+                .set(M::login_throttle_window_end(), window_end)
+                .exec()
+                .await?;
+            if updated > 0 {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Forgets the failed logins for the identifier after a successful login
+///
+/// The client ip's failures are kept, so an attacker can't reset them by logging into their own account.
+pub(crate) async fn reset<M: AuthModels>(
+    tx: &mut Transaction,
+    keys: &ThrottleKeys,
+) -> ApiResult<()> {
+    rorm::delete!(&mut *tx, M::LoginThrottle)
+        .condition(M::login_throttle_key().equals(&keys.identifier))
+        .await?;
+    Ok(())
+}
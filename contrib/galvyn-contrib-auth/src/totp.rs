@@ -0,0 +1,45 @@
+//! Generation and verification of time-based one-time passwords (RFC 6238)
+
+use crate::utils::unix_now;
+use totp_rs::{Algorithm, Secret, SecretParseError, TotpUrlError, TOTP};
+
+/// Generates a new random secret
+pub(crate) fn generate_secret() -> Result<Vec<u8>, SecretParseError> {
+    Secret::generate_secret().to_bytes()
+}
+
+/// Constructs a TOTP generator using the parameters supported by common authenticator apps
+///
+/// (SHA-1, 6 digits and a 30 seconds step, accepting the previous and next code as well)
+pub(crate) fn totp(
+    secret: Vec<u8>,
+    issuer: &str,
+    account_name: String,
+) -> Result<TOTP, TotpUrlError> {
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(issuer.to_string()),
+        account_name,
+    )
+}
+
+/// Checks a code against a secret
+///
+/// Returns the time step the code belongs to,
+/// which has to be later than the key's last used one to prevent replays.
+pub(crate) fn verify_totp(secret: &[u8], issuer: &str, code: &str) -> Option<i64> {
+    let totp = totp(secret.to_vec(), issuer, String::new()).ok()?;
+    let skew = u64::from(totp.skew);
+    let current = unix_now() as u64 / totp.step;
+
+    // Check every accepted step on its own to learn which one the code belongs to
+    let exact = TOTP { skew: 0, ..totp };
+    let code = code.trim();
+    (current.saturating_sub(skew)..=current + skew)
+        .find(|step| exact.check(code, step * exact.step))
+        .map(|step| step as i64)
+}
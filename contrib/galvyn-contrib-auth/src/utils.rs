@@ -0,0 +1,51 @@
+//! Small helpers shared by the handlers
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Generates a random token with 256 bits of entropy encoded as 64 hex characters
+pub(crate) fn generate_token() -> String {
+    let mut bytes = [0; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Hashes a token generated by [`generate_token`] for storing and looking it up
+///
+/// The tokens have enough entropy that a fast hash suffices.
+pub(crate) fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Generates a token with [`generate_token`] together with the hash to store it as
+///
+/// Handlers look the token up by the [`hash_token`] of what the client sends.
+pub(crate) fn generate_hashed_token() -> (String, String) {
+    let token = generate_token();
+    let token_hash = hash_token(&token);
+    (token, token_hash)
+}
+
+/// The current point in time in seconds since the unix epoch
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_delivered_token_is_redeemed_by_its_stored_hash() {
+        let (token, token_hash) = generate_hashed_token();
+        assert_ne!(token, token_hash);
+        assert_eq!(hash_token(&token), token_hash);
+    }
+}
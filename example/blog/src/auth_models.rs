@@ -1,3 +1,4 @@
+use galvyn::contrib::auth::audit::AuthEventKind;
 use galvyn::contrib::auth::MaybeAttestedPasskey;
 use rorm::fields::types::Json;
 use rorm::internal::field::{Field, FieldProxy};
@@ -11,6 +12,8 @@ pub struct Account {
 
     #[rorm(unique, max_length = 255)]
     pub id: String,
+
+    pub disabled_at: Option<i64>,
 }
 
 #[derive(Model)]
@@ -32,7 +35,170 @@ pub struct LocalAccount {
     #[rorm(max_length = 1024)]
     pub password: Option<String>,
 
+    #[rorm(default = 0)]
+    pub failed_logins: i32,
+
+    pub locked_until: Option<i64>,
+
+    #[rorm(default = false)]
+    pub verified: bool,
+
+    #[rorm(unique, max_length = 64)]
+    pub verification_token_hash: Option<String>,
+
+    pub verification_expires_at: Option<i64>,
+
+    pub account: ForeignModel<Account>,
+}
+
+#[derive(Model)]
+pub struct PasswordResetToken {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
+    pub account: ForeignModel<Account>,
+
+    #[rorm(unique, max_length = 64)]
+    pub token_hash: String,
+
+    pub expires_at: i64,
+}
+
+#[derive(Model)]
+pub struct LoginThrottle {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(unique, max_length = 255)]
+    pub key: String,
+
+    pub failures: i32,
+
+    pub window_end: i64,
+}
+
+#[derive(Model)]
+pub struct Role {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(unique, max_length = 255)]
+    pub name: String,
+}
+
+#[derive(Model)]
+pub struct RolePermission {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
+    pub role: ForeignModel<Role>,
+
+    #[rorm(max_length = 255)]
+    pub name: String,
+}
+
+#[derive(Model)]
+pub struct AccountRole {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
+    pub account: ForeignModel<Account>,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
+    pub role: ForeignModel<Role>,
+}
+
+#[derive(Model)]
+pub struct ApiKey {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
+    pub account: ForeignModel<Account>,
+
+    #[rorm(unique, max_length = 255)]
+    pub id: String,
+
+    #[rorm(max_length = 255)]
+    pub label: String,
+
+    #[rorm(unique, max_length = 64)]
+    pub secret_hash: String,
+
+    pub scopes: Json<Vec<String>>,
+
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Model)]
+pub struct AccountSession {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
+    pub account: ForeignModel<Account>,
+
+    #[rorm(unique, max_length = 64)]
+    pub id: String,
+
+    #[rorm(unique, max_length = 255)]
+    pub session_id: String,
+
+    pub created_at: i64,
+}
+
+#[derive(Model)]
+pub struct OidcSession {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(unique, max_length = 255)]
+    pub session_id: String,
+
+    #[rorm(max_length = 255)]
+    pub subject: String,
+
+    #[rorm(max_length = 255)]
+    pub sid: String,
+}
+
+#[derive(Model)]
+pub struct RefreshToken {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
+    pub account: ForeignModel<Account>,
+
+    #[rorm(unique, max_length = 64)]
+    pub token_hash: String,
+
+    pub expires_at: i64,
+}
+
+#[derive(Model)]
+pub struct AuthEvent {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
     pub account: ForeignModel<Account>,
+
+    pub kind: AuthEventKind,
+
+    #[rorm(max_length = 255)]
+    pub ip: Option<String>,
+
+    #[rorm(max_length = 255)]
+    pub user_agent: Option<String>,
+
+    #[rorm(max_length = 255)]
+    pub actor: Option<String>,
+
+    pub created_at: i64,
 }
 
 #[derive(Model)]
@@ -48,6 +214,20 @@ pub struct TotpKey {
 
     #[rorm(max_length = 32)]
     pub secret: Vec<u8>,
+
+    pub last_used_step: i64,
+}
+
+#[derive(Model)]
+pub struct RecoveryCode {
+    #[rorm(id)]
+    pub pk: i64,
+
+    #[rorm(on_delete = "Cascade", on_update = "Cascade")]
+    pub local_account: ForeignModel<LocalAccount>,
+
+    #[rorm(max_length = 64)]
+    pub code_hash: String,
 }
 
 #[derive(Model)]
@@ -72,6 +252,11 @@ impl galvyn::contrib::auth::AuthModels for AuthModels {
         Account::F.id
     }
 
+    fn account_disabled_at(
+    ) -> FieldProxy<impl Field<Type = Option<i64>, Model = Self::Account>, Self::Account> {
+        Account::F.disabled_at
+    }
+
     fn insertable_account(id: String) -> impl Patch<Model = Self::Account> {
         #[derive(Patch)]
         #[rorm(model = "Account")]
@@ -134,6 +319,489 @@ impl galvyn::contrib::auth::AuthModels for AuthModels {
         LocalAccount::F.password
     }
 
+    fn local_account_verified(
+    ) -> FieldProxy<impl Field<Type = bool, Model = Self::LocalAccount>, Self::LocalAccount> {
+        LocalAccount::F.verified
+    }
+
+    fn local_account_verification_token_hash(
+    ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::LocalAccount>, Self::LocalAccount>
+    {
+        LocalAccount::F.verification_token_hash
+    }
+
+    fn local_account_verification_expires_at(
+    ) -> FieldProxy<impl Field<Type = Option<i64>, Model = Self::LocalAccount>, Self::LocalAccount>
+    {
+        LocalAccount::F.verification_expires_at
+    }
+
+    fn insertable_local_account(
+        password: Option<String>,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::LocalAccount> {
+        #[derive(Patch)]
+        #[rorm(model = "LocalAccount")]
+        struct InsertableLocalAccount {
+            password: Option<String>,
+            account: ForeignModel<Account>,
+        }
+
+        InsertableLocalAccount {
+            password,
+            account: ForeignModelByField::Key(*account_pk),
+        }
+    }
+
+    fn local_account_failed_logins(
+    ) -> FieldProxy<impl Field<Type = i32, Model = Self::LocalAccount>, Self::LocalAccount> {
+        LocalAccount::F.failed_logins
+    }
+
+    fn local_account_locked_until(
+    ) -> FieldProxy<impl Field<Type = Option<i64>, Model = Self::LocalAccount>, Self::LocalAccount>
+    {
+        LocalAccount::F.locked_until
+    }
+
+    type PasswordResetToken = PasswordResetToken;
+
+    fn password_reset_token_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::PasswordResetToken,
+        >,
+        Self::PasswordResetToken,
+    > {
+        PasswordResetToken::F.account
+    }
+
+    fn password_reset_token_token_hash() -> FieldProxy<
+        impl Field<Type = String, Model = Self::PasswordResetToken>,
+        Self::PasswordResetToken,
+    > {
+        PasswordResetToken::F.token_hash
+    }
+
+    fn password_reset_token_expires_at() -> FieldProxy<
+        impl Field<Type = i64, Model = Self::PasswordResetToken>,
+        Self::PasswordResetToken,
+    > {
+        PasswordResetToken::F.expires_at
+    }
+
+    fn insertable_password_reset_token(
+        token_hash: String,
+        expires_at: i64,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::PasswordResetToken> {
+        #[derive(Patch)]
+        #[rorm(model = "PasswordResetToken")]
+        struct InsertablePasswordResetToken {
+            account: ForeignModel<Account>,
+            token_hash: String,
+            expires_at: i64,
+        }
+
+        InsertablePasswordResetToken {
+            account: ForeignModelByField::Key(*account_pk),
+            token_hash,
+            expires_at,
+        }
+    }
+
+    type LoginThrottle = LoginThrottle;
+
+    fn login_throttle_key(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::LoginThrottle>, Self::LoginThrottle>
+    {
+        LoginThrottle::F.key
+    }
+
+    fn login_throttle_failures(
+    ) -> FieldProxy<impl Field<Type = i32, Model = Self::LoginThrottle>, Self::LoginThrottle> {
+        LoginThrottle::F.failures
+    }
+
+    fn login_throttle_window_end(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::LoginThrottle>, Self::LoginThrottle> {
+        LoginThrottle::F.window_end
+    }
+
+    fn insertable_login_throttle(
+        key: String,
+        failures: i32,
+        window_end: i64,
+    ) -> impl Patch<Model = Self::LoginThrottle> {
+        #[derive(Patch)]
+        #[rorm(model = "LoginThrottle")]
+        struct InsertableLoginThrottle {
+            key: String,
+            failures: i32,
+            window_end: i64,
+        }
+
+        InsertableLoginThrottle {
+            key,
+            failures,
+            window_end,
+        }
+    }
+
+    type Role = Role;
+
+    fn role_name() -> FieldProxy<impl Field<Type = String, Model = Self::Role>, Self::Role> {
+        Role::F.name
+    }
+
+    fn insertable_role(name: String) -> impl Patch<Model = Self::Role> {
+        #[derive(Patch)]
+        #[rorm(model = "Role")]
+        struct InsertableRole {
+            name: String,
+        }
+
+        InsertableRole { name }
+    }
+
+    type RolePermission = RolePermission;
+
+    fn role_permission_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Role as Model>::Primary>,
+            Model = Self::RolePermission,
+        >,
+        Self::RolePermission,
+    > {
+        RolePermission::F.role
+    }
+
+    fn role_permission_name(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::RolePermission>, Self::RolePermission>
+    {
+        RolePermission::F.name
+    }
+
+    fn insertable_role_permission(
+        name: String,
+        role_pk: &<<Self::Role as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::RolePermission> {
+        #[derive(Patch)]
+        #[rorm(model = "RolePermission")]
+        struct InsertableRolePermission {
+            role: ForeignModel<Role>,
+            name: String,
+        }
+
+        InsertableRolePermission {
+            role: ForeignModelByField::Key(*role_pk),
+            name,
+        }
+    }
+
+    type AccountRole = AccountRole;
+
+    fn account_role_account_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::AccountRole,
+        >,
+        Self::AccountRole,
+    > {
+        AccountRole::F.account
+    }
+
+    fn account_role_role_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Role as Model>::Primary>,
+            Model = Self::AccountRole,
+        >,
+        Self::AccountRole,
+    > {
+        AccountRole::F.role
+    }
+
+    fn insertable_account_role(
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+        role_pk: &<<Self::Role as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::AccountRole> {
+        #[derive(Patch)]
+        #[rorm(model = "AccountRole")]
+        struct InsertableAccountRole {
+            account: ForeignModel<Account>,
+            role: ForeignModel<Role>,
+        }
+
+        InsertableAccountRole {
+            account: ForeignModelByField::Key(*account_pk),
+            role: ForeignModelByField::Key(*role_pk),
+        }
+    }
+
+    type ApiKey = ApiKey;
+
+    fn api_key_fm() -> FieldProxy<
+        impl Field<Type = ForeignModelByField<<Self::Account as Model>::Primary>, Model = Self::ApiKey>,
+        Self::ApiKey,
+    > {
+        ApiKey::F.account
+    }
+
+    fn api_key_id() -> FieldProxy<impl Field<Type = String, Model = Self::ApiKey>, Self::ApiKey> {
+        ApiKey::F.id
+    }
+
+    fn api_key_label() -> FieldProxy<impl Field<Type = String, Model = Self::ApiKey>, Self::ApiKey>
+    {
+        ApiKey::F.label
+    }
+
+    fn api_key_secret_hash(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::ApiKey>, Self::ApiKey> {
+        ApiKey::F.secret_hash
+    }
+
+    fn api_key_scopes(
+    ) -> FieldProxy<impl Field<Type = Json<Vec<String>>, Model = Self::ApiKey>, Self::ApiKey> {
+        ApiKey::F.scopes
+    }
+
+    fn api_key_expires_at(
+    ) -> FieldProxy<impl Field<Type = Option<i64>, Model = Self::ApiKey>, Self::ApiKey> {
+        ApiKey::F.expires_at
+    }
+
+    fn insertable_api_key(
+        id: String,
+        label: String,
+        secret_hash: String,
+        scopes: Vec<String>,
+        expires_at: Option<i64>,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::ApiKey> {
+        #[derive(Patch)]
+        #[rorm(model = "ApiKey")]
+        struct InsertableApiKey {
+            account: ForeignModel<Account>,
+            id: String,
+            label: String,
+            secret_hash: String,
+            scopes: Json<Vec<String>>,
+            expires_at: Option<i64>,
+        }
+
+        InsertableApiKey {
+            account: ForeignModelByField::Key(*account_pk),
+            id,
+            label,
+            secret_hash,
+            scopes: Json(scopes),
+            expires_at,
+        }
+    }
+
+    type AccountSession = AccountSession;
+
+    fn account_session_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::AccountSession,
+        >,
+        Self::AccountSession,
+    > {
+        AccountSession::F.account
+    }
+
+    fn account_session_id(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::AccountSession>, Self::AccountSession>
+    {
+        AccountSession::F.id
+    }
+
+    fn account_session_session_id(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::AccountSession>, Self::AccountSession>
+    {
+        AccountSession::F.session_id
+    }
+
+    fn account_session_created_at(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::AccountSession>, Self::AccountSession>
+    {
+        AccountSession::F.created_at
+    }
+
+    fn insertable_account_session(
+        id: String,
+        session_id: String,
+        created_at: i64,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::AccountSession> {
+        #[derive(Patch)]
+        #[rorm(model = "AccountSession")]
+        struct InsertableAccountSession {
+            account: ForeignModel<Account>,
+            id: String,
+            session_id: String,
+            created_at: i64,
+        }
+
+        InsertableAccountSession {
+            account: ForeignModelByField::Key(*account_pk),
+            id,
+            session_id,
+            created_at,
+        }
+    }
+
+    type OidcSession = OidcSession;
+
+    fn oidc_session_session_id(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::OidcSession>, Self::OidcSession> {
+        OidcSession::F.session_id
+    }
+
+    fn oidc_session_subject(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::OidcSession>, Self::OidcSession> {
+        OidcSession::F.subject
+    }
+
+    fn oidc_session_sid(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::OidcSession>, Self::OidcSession> {
+        OidcSession::F.sid
+    }
+
+    fn insertable_oidc_session(
+        session_id: String,
+        subject: String,
+        sid: String,
+    ) -> impl Patch<Model = Self::OidcSession> {
+        #[derive(Patch)]
+        #[rorm(model = "OidcSession")]
+        struct InsertableOidcSession {
+            session_id: String,
+            subject: String,
+            sid: String,
+        }
+
+        InsertableOidcSession {
+            session_id,
+            subject,
+            sid,
+        }
+    }
+
+    type RefreshToken = RefreshToken;
+
+    fn refresh_token_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::RefreshToken,
+        >,
+        Self::RefreshToken,
+    > {
+        RefreshToken::F.account
+    }
+
+    fn refresh_token_token_hash(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::RefreshToken>, Self::RefreshToken> {
+        RefreshToken::F.token_hash
+    }
+
+    fn refresh_token_expires_at(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::RefreshToken>, Self::RefreshToken> {
+        RefreshToken::F.expires_at
+    }
+
+    fn insertable_refresh_token(
+        token_hash: String,
+        expires_at: i64,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::RefreshToken> {
+        #[derive(Patch)]
+        #[rorm(model = "RefreshToken")]
+        struct InsertableRefreshToken {
+            account: ForeignModel<Account>,
+            token_hash: String,
+            expires_at: i64,
+        }
+
+        InsertableRefreshToken {
+            account: ForeignModelByField::Key(*account_pk),
+            token_hash,
+            expires_at,
+        }
+    }
+
+    type AuthEvent = AuthEvent;
+
+    fn auth_event_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::Account as Model>::Primary>,
+            Model = Self::AuthEvent,
+        >,
+        Self::AuthEvent,
+    > {
+        AuthEvent::F.account
+    }
+
+    fn auth_event_kind(
+    ) -> FieldProxy<impl Field<Type = AuthEventKind, Model = Self::AuthEvent>, Self::AuthEvent>
+    {
+        AuthEvent::F.kind
+    }
+
+    fn auth_event_ip(
+    ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::AuthEvent>, Self::AuthEvent>
+    {
+        AuthEvent::F.ip
+    }
+
+    fn auth_event_user_agent(
+    ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::AuthEvent>, Self::AuthEvent>
+    {
+        AuthEvent::F.user_agent
+    }
+
+    fn auth_event_created_at(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::AuthEvent>, Self::AuthEvent> {
+        AuthEvent::F.created_at
+    }
+
+    fn auth_event_actor(
+    ) -> FieldProxy<impl Field<Type = Option<String>, Model = Self::AuthEvent>, Self::AuthEvent>
+    {
+        AuthEvent::F.actor
+    }
+
+    fn insertable_auth_event(
+        kind: AuthEventKind,
+        ip: Option<String>,
+        user_agent: Option<String>,
+        actor: Option<String>,
+        created_at: i64,
+        account_pk: &<<Self::Account as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::AuthEvent> {
+        #[derive(Patch)]
+        #[rorm(model = "AuthEvent")]
+        struct InsertableAuthEvent {
+            account: ForeignModel<Account>,
+            kind: AuthEventKind,
+            ip: Option<String>,
+            user_agent: Option<String>,
+            actor: Option<String>,
+            created_at: i64,
+        }
+
+        InsertableAuthEvent {
+            account: ForeignModelByField::Key(*account_pk),
+            kind,
+            ip,
+            user_agent,
+            actor,
+            created_at,
+        }
+    }
+
     type TotpKey = TotpKey;
 
     fn totp_key_fm() -> FieldProxy<
@@ -146,6 +814,73 @@ impl galvyn::contrib::auth::AuthModels for AuthModels {
         TotpKey::F.local_account
     }
 
+    fn totp_key_secret(
+    ) -> FieldProxy<impl Field<Type = Vec<u8>, Model = Self::TotpKey>, Self::TotpKey> {
+        TotpKey::F.secret
+    }
+
+    fn totp_key_last_used_step(
+    ) -> FieldProxy<impl Field<Type = i64, Model = Self::TotpKey>, Self::TotpKey> {
+        TotpKey::F.last_used_step
+    }
+
+    fn insertable_totp_key(
+        label: String,
+        secret: Vec<u8>,
+        last_used_step: i64,
+        local_account_pk: &<<Self::LocalAccount as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::TotpKey> {
+        #[derive(Patch)]
+        #[rorm(model = "TotpKey")]
+        struct InsertableTotpKey {
+            local_account: ForeignModel<LocalAccount>,
+            label: String,
+            secret: Vec<u8>,
+            last_used_step: i64,
+        }
+
+        InsertableTotpKey {
+            local_account: ForeignModelByField::Key(*local_account_pk),
+            label,
+            secret,
+            last_used_step,
+        }
+    }
+
+    type RecoveryCode = RecoveryCode;
+
+    fn recovery_code_fm() -> FieldProxy<
+        impl Field<
+            Type = ForeignModelByField<<Self::LocalAccount as Model>::Primary>,
+            Model = Self::RecoveryCode,
+        >,
+        Self::RecoveryCode,
+    > {
+        RecoveryCode::F.local_account
+    }
+
+    fn recovery_code_code_hash(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::RecoveryCode>, Self::RecoveryCode> {
+        RecoveryCode::F.code_hash
+    }
+
+    fn insertable_recovery_code(
+        code_hash: String,
+        local_account_pk: &<<Self::LocalAccount as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::RecoveryCode> {
+        #[derive(Patch)]
+        #[rorm(model = "RecoveryCode")]
+        struct InsertableRecoveryCode {
+            local_account: ForeignModel<LocalAccount>,
+            code_hash: String,
+        }
+
+        InsertableRecoveryCode {
+            local_account: ForeignModelByField::Key(*local_account_pk),
+            code_hash,
+        }
+    }
+
     type WebauthnKey = WebAuthnKey;
 
     fn webauthn_key_fm() -> FieldProxy<
@@ -164,4 +899,29 @@ impl galvyn::contrib::auth::AuthModels for AuthModels {
     > {
         WebAuthnKey::F.key
     }
+
+    fn webauthn_key_label(
+    ) -> FieldProxy<impl Field<Type = String, Model = Self::WebauthnKey>, Self::WebauthnKey> {
+        WebAuthnKey::F.label
+    }
+
+    fn insertable_webauthn_key(
+        label: String,
+        key: MaybeAttestedPasskey,
+        local_account_pk: &<<Self::LocalAccount as Model>::Primary as Field>::Type,
+    ) -> impl Patch<Model = Self::WebauthnKey> {
+        #[derive(Patch)]
+        #[rorm(model = "WebAuthnKey")]
+        struct InsertableWebAuthnKey {
+            local_account: ForeignModel<LocalAccount>,
+            label: String,
+            key: Json<MaybeAttestedPasskey>,
+        }
+
+        InsertableWebAuthnKey {
+            local_account: ForeignModelByField::Key(*local_account_pk),
+            label,
+            key: Json(key),
+        }
+    }
 }
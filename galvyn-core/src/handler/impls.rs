@@ -1,12 +1,14 @@
 use super::request_body::{RequestBody, ShouldBeRequestBody};
-use super::request_part::{RequestPart, ShouldBeRequestPart};
+use super::request_part::{PathParameters, QueryParameter, RequestPart, ShouldBeRequestPart};
 use crate::handler::response_body::{ResponseBody, ShouldBeResponseBody};
+use crate::handler::response_part::ResponseHeader;
 use crate::schema_generator::SchemaGenerator;
 use axum::body::Bytes;
 use axum::extract::Path;
 use axum::extract::Query;
 use axum::extract::RawForm;
-use axum::http::{header, HeaderName, StatusCode};
+use axum::extract::RawQuery;
+use axum::http::{header, StatusCode};
 use axum::response::{Html, Redirect};
 use axum::Form;
 use axum::Json;
@@ -66,6 +68,13 @@ impl HandlerArgument for RawForm {
 */
 impl<T> ShouldBeRequestPart for Path<T> {}
 impl<T: DeserializeOwned + JsonSchema> RequestPart for Path<T> {
+    fn path_parameters(gen: &mut SchemaGenerator) -> Option<PathParameters> {
+        Some(match gen.generate_object::<T>() {
+            Some(object) => PathParameters::Named(object.properties.keys().cloned().collect()),
+            None => PathParameters::Unnamed,
+        })
+    }
+
     // fn parameters(gen: &mut SchemaGenerator, path: &[&str]) -> Vec<Parameter> {
     //     let Ok(schema) = gen.generate_refless::<T>() else {
     //         warn!("Unsupported handler argument: {}", type_name::<Self>());
@@ -122,6 +131,21 @@ impl<T: DeserializeOwned + JsonSchema> RequestPart for Path<T> {
 
 impl<T> ShouldBeRequestPart for Query<T> {}
 impl<T: DeserializeOwned + JsonSchema> RequestPart for Query<T> {
+    fn query_parameters(gen: &mut SchemaGenerator) -> Vec<QueryParameter> {
+        let Some(object) = gen.generate_object::<T>() else {
+            return Vec::new();
+        };
+        object
+            .properties
+            .into_iter()
+            .map(|(name, schema)| QueryParameter {
+                required: object.required.contains(&name),
+                name,
+                schema,
+            })
+            .collect()
+    }
+
     // fn parameters(gen: &mut SchemaGenerator, _path: &[&str]) -> Vec<Parameter> {
     //     let Some((obj, _)) = gen.generate_object::<T>() else {
     //         warn!("Unsupported handler argument: {}", type_name::<Self>());
@@ -150,6 +174,9 @@ impl<T: DeserializeOwned + JsonSchema> RequestPart for Query<T> {
     // }
 }
 
+impl ShouldBeRequestPart for RawQuery {}
+impl RequestPart for RawQuery {}
+
 impl ShouldBeResponseBody for &'static str {}
 impl ResponseBody for &'static str {
     fn body(_gen: &mut SchemaGenerator) -> Vec<(StatusCode, Option<(Mime, Option<Schema>)>)> {
@@ -263,6 +290,12 @@ where
     T: ResponseBody,
     E: ResponseBody,
 {
+    fn header(gen: &mut SchemaGenerator) -> Vec<ResponseHeader> {
+        let mut headers = T::header(&mut *gen);
+        headers.extend(E::header(&mut *gen));
+        headers
+    }
+
     fn body(_gen: &mut SchemaGenerator) -> Vec<(StatusCode, Option<(Mime, Option<Schema>)>)> {
         let mut bodies = T::body(&mut *_gen);
         bodies.extend(E::body(&mut *_gen));
@@ -272,8 +305,8 @@ where
 
 impl ShouldBeResponseBody for Redirect {}
 impl ResponseBody for Redirect {
-    fn header() -> Vec<HeaderName> {
-        vec![header::LOCATION]
+    fn header(_gen: &mut SchemaGenerator) -> Vec<ResponseHeader> {
+        vec![ResponseHeader::new(header::LOCATION).description("The url to redirect to")]
     }
 
     fn body(_gen: &mut SchemaGenerator) -> Vec<(StatusCode, Option<(Mime, Option<Schema>)>)> {
@@ -332,3 +365,22 @@ impl<T> ResponseBody for Html<T> {
         vec![(StatusCode::OK, Some((mime::TEXT_HTML_UTF_8, None)))]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use axum::http::header;
+    use axum::response::Redirect;
+    use schemars::Map;
+
+    use crate::handler::response_body::ResponseBody;
+    use crate::schema_generator::SchemaGenerator;
+    use crate::stuff::api_error::ApiResult;
+
+    #[test]
+    fn api_result_documents_the_headers_of_its_ok_type() {
+        let headers = SchemaGenerator::employ(&mut Map::new(), |gen| {
+            <ApiResult<Redirect> as ResponseBody>::header(gen)
+        });
+        assert!(headers.iter().any(|header| header.name == header::LOCATION));
+    }
+}
@@ -42,15 +42,28 @@ pub struct HandlerMeta {
     /// `true` if `#[deprecated]` is present
     pub deprecated: bool,
 
+    /// Set through `#[handler(..., experimental = true)]` for handlers which aren't production-ready yet
+    pub experimental: bool,
+
     /// Set by macro if `#[doc = "..."]` (i.e. a doc comment) is present
     pub doc: &'static [&'static str],
 
     /// The handler's identifier
     pub ident: &'static str,
 
+    /// The OpenAPI operation's id set through `#[handler(..., operation_id = "...")]`
+    ///
+    /// Defaults to the handler's identifier.
+    pub operation_id: &'static str,
+
     /// Tags set through `#[operation(..., tags(...))]`
     pub tags: &'static [&'static str],
 
+    /// The request content types set through `#[handler(..., accept(...))]`
+    ///
+    /// If this is not empty, requests with any other content type are rejected.
+    pub accept: &'static [&'static str],
+
     pub request_parts: Vec<RequestPartMetadata>,
 
     pub request_body: Option<RequestBodyMetadata>,
@@ -60,6 +73,12 @@ pub struct HandlerMeta {
     pub response_parts: Vec<ResponsePartMetadata>,
 
     pub response_body: Option<ResponseBodyMetadata>,
+
+    /// An example request body set through `#[handler(..., example(request = ...))]`
+    pub request_example: Option<fn() -> serde_json::Value>,
+
+    /// An example response body set through `#[handler(..., example(response = ...))]`
+    pub response_example: Option<fn() -> serde_json::Value>,
 }
 
 #[derive(Clone, Debug)]
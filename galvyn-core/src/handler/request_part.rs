@@ -1,16 +1,59 @@
 use crate::macro_utils::type_metadata::{HasMetadata, ShouldHaveMetadata};
+use crate::schema_generator::SchemaGenerator;
+use schemars::schema::Schema;
 
 /// Describes the behaviour of a type implementing [`FromRequestParts`](axum::extract::FromRequestParts)
-pub trait RequestPart: ShouldBeRequestPart {}
+pub trait RequestPart: ShouldBeRequestPart {
+    /// The path parameters this extractor consumes
+    ///
+    /// Returns `None` for extractors which don't read the request's path.
+    fn path_parameters(_gen: &mut SchemaGenerator) -> Option<PathParameters> {
+        None
+    }
+
+    /// The query parameters this extractor consumes
+    fn query_parameters(_gen: &mut SchemaGenerator) -> Vec<QueryParameter> {
+        Vec::new()
+    }
+}
 
 pub trait ShouldBeRequestPart {}
 
+/// The path parameters consumed by an extractor like [`Path`](axum::extract::Path)
+#[derive(Clone, Debug)]
+pub enum PathParameters {
+    /// The parameters are deserialized into a struct whose fields name them
+    Named(Vec<String>),
+
+    /// The parameters are deserialized by position (i.e. into a single value or a tuple)
+    Unnamed,
+}
+
+/// A query parameter consumed by an extractor like [`Query`](axum::extract::Query)
 #[derive(Clone, Debug)]
-pub struct RequestPartMetadata {}
+pub struct QueryParameter {
+    /// The parameter's name
+    pub name: String,
+
+    /// Whether the parameter has to be present
+    pub required: bool,
+
+    /// The parameter value's schema
+    pub schema: Schema,
+}
+
+#[derive(Clone, Debug)]
+pub struct RequestPartMetadata {
+    pub path_parameters: fn(&mut SchemaGenerator) -> Option<PathParameters>,
+    pub query_parameters: fn(&mut SchemaGenerator) -> Vec<QueryParameter>,
+}
 
 impl<T: ShouldBeRequestPart> ShouldHaveMetadata<RequestPartMetadata> for T {}
 impl<T: RequestPart> HasMetadata<RequestPartMetadata> for T {
     fn metadata() -> RequestPartMetadata {
-        RequestPartMetadata {}
+        RequestPartMetadata {
+            path_parameters: T::path_parameters,
+            query_parameters: T::query_parameters,
+        }
     }
 }
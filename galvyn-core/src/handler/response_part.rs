@@ -1,16 +1,18 @@
 use crate::macro_utils::type_metadata::{HasMetadata, ShouldHaveMetadata};
+use crate::schema_generator::SchemaGenerator;
 use axum::http::HeaderName;
+use schemars::schema::Schema;
 
 /// Describes the behaviour of a type implementing [`IntoResponseParts`](axum::response::IntoResponseParts)
 pub trait ResponsePart: ShouldBeResponsePart {
-    fn header() -> Vec<HeaderName>;
+    fn header(gen: &mut SchemaGenerator) -> Vec<ResponseHeader>;
 }
 
 pub trait ShouldBeResponsePart {}
 
 #[derive(Clone, Debug)]
 pub struct ResponsePartMetadata {
-    pub header: fn() -> Vec<HeaderName>,
+    pub header: fn(&mut SchemaGenerator) -> Vec<ResponseHeader>,
 }
 
 impl<T: ShouldBeResponsePart> ShouldHaveMetadata<ResponsePartMetadata> for T {}
@@ -19,3 +21,41 @@ impl<T: ResponsePart> HasMetadata<ResponsePartMetadata> for T {
         ResponsePartMetadata { header: T::header }
     }
 }
+
+/// A header set by a [`ResponsePart`] or [`ResponseBody`](crate::handler::response_body::ResponseBody)
+#[derive(Clone, Debug)]
+pub struct ResponseHeader {
+    /// The header's name
+    pub name: HeaderName,
+
+    /// A description of the header's value
+    pub description: Option<&'static str>,
+
+    /// The schema of the header's value
+    ///
+    /// If it is `None`, the value is documented as string.
+    pub schema: Option<Schema>,
+}
+
+impl ResponseHeader {
+    /// Constructs a header without description and schema
+    pub fn new(name: HeaderName) -> Self {
+        Self {
+            name,
+            description: None,
+            schema: None,
+        }
+    }
+
+    /// Sets the header's description
+    pub fn description(mut self, description: &'static str) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    /// Sets the schema of the header's value
+    pub fn schema(mut self, schema: Schema) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+}
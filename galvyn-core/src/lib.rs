@@ -1,10 +1,21 @@
 pub use self::handler::GalvynHandler;
+pub use self::router::GalvynRoute;
 pub use self::router::GalvynRouter;
+pub use self::router::RouteIssue;
+pub use self::router::RouteIssueKind;
+pub use self::router::SecuritySchemeKind;
+pub use self::router::SecuritySchemeMetadata;
 pub use crate::module::*;
 
 pub mod re_exports {
     pub use axum;
+    pub use mime;
     pub use rorm;
+    pub use schemars;
+    pub use serde;
+    pub use serde_json;
+    pub use tower_sessions;
+    pub use uuid;
 }
 
 pub mod handler;
@@ -1,7 +1,7 @@
 use crate::module;
-use crate::module::registry::module_set::OwnedModulesSet;
+use crate::module::registry::module_set::{LeakedModuleSet, OwnedModulesSet};
 use crate::module::registry::ModuleDependencies;
-use crate::module::registry::{DynModule, Registry};
+use crate::module::registry::{DynModule, Registry, THREAD_LOCAL};
 use crate::module::Module;
 use futures_concurrency::future::Join;
 use futures_lite::future;
@@ -104,8 +104,57 @@ impl RegistryBuilder {
     /// Initialized all registered modules
     ///
     /// and makes the registry available through [`Registry::global`].
+    ///
+    /// # Errors
+    /// Returns [`InitError::AlreadyInitialized`] without initializing any module
+    /// if the registry has already been initialized.
+    /// Tests which each start an application should use [`RegistryBuilder::init_for_current_thread`] instead.
     #[instrument(level = "trace", name = "RegistryBuilder::init", skip(self))]
     pub async fn init(&mut self) -> Result<(), InitError> {
+        if Registry::raw_global().get().is_some() {
+            return Err(InitError::AlreadyInitialized);
+        }
+
+        let modules = self.init_modules().await?;
+
+        let registry = {
+            let global = Registry::raw_global();
+            if global.set(Registry { modules }).is_err() {
+                return Err(InitError::AlreadyInitialized);
+            }
+            global
+                .get()
+                .unwrap_or_else(|| unreachable!("The OnceLock has just been set"))
+        };
+
+        post_init_modules(registry).await
+    }
+
+    /// Initializes all registered modules for the current thread only
+    ///
+    /// and makes the registry available through [`Registry::global`] on this thread,
+    /// taking precedence over the process-global registry.
+    /// Calling this again replaces the thread's registry with freshly initialized modules.
+    ///
+    /// This is meant for tests: every test runs on its own thread and gets its own module instances.
+    /// Use a current-thread runtime (the default of `#[tokio::test]`),
+    /// because tasks running on other threads don't see this registry.
+    #[instrument(
+        level = "trace",
+        name = "RegistryBuilder::init_for_current_thread",
+        skip(self)
+    )]
+    pub async fn init_for_current_thread(&mut self) -> Result<(), InitError> {
+        let modules = self.init_modules().await?;
+
+        let registry: &'static Registry = Box::leak(Box::new(Registry { modules }));
+        THREAD_LOCAL.set(Some(registry));
+
+        post_init_modules(registry).await
+    }
+
+    /// Runs the `pre_init` and `init` of all registered modules
+    async fn init_modules(&mut self) -> Result<LeakedModuleSet, InitError> {
         let pre_init_modules = process_join_results(
             self.modules
                 .drain(..)
@@ -123,35 +172,24 @@ impl RegistryBuilder {
                 .await
                 .map_err(InitError::Init)?;
         }
+        Ok(modules.leak())
+    }
+}
 
-        let registry = {
-            let global = Registry::raw_global();
-            if global
-                .set(Registry {
-                    modules: modules.leak(),
-                })
-                .is_err()
-            {
-                panic!("The module registry has already been initialized once");
-            }
-            global
-                .get()
-                .unwrap_or_else(|| unreachable!("The OnceLock has just been set"))
-        };
-
-        process_join_results(
-            registry
-                .modules
-                .iter()
-                .map(|init_module| init_module.post_init())
-                .collect::<Vec<_>>()
-                .join()
-                .await,
-        )
-        .map_err(InitError::PostInit)?;
+/// Runs the `post_init` of all modules once they are available through `registry`
+async fn post_init_modules(registry: &'static Registry) -> Result<(), InitError> {
+    process_join_results(
+        registry
+            .modules
+            .iter()
+            .map(|init_module| init_module.post_init())
+            .collect::<Vec<_>>()
+            .join()
+            .await,
+    )
+    .map_err(InitError::PostInit)?;
 
-        Ok(())
-    }
+    Ok(())
 }
 
 #[derive(Debug)]
@@ -159,23 +197,24 @@ pub enum InitError {
     PreInit(Vec<module::PreInitError>),
     Init(module::InitError),
     PostInit(Vec<module::PostInitError>),
+    /// The registry has already been initialized (by another call to [`RegistryBuilder::init`])
+    AlreadyInitialized,
 }
 
 impl fmt::Display for InitError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let phase = match self {
-            InitError::PreInit(_) => "pre-",
-            InitError::Init(_) => "",
-            InitError::PostInit(_) => "post-",
+        let (phase, errors) = match self {
+            InitError::PreInit(errors) => ("pre-", errors.split_first()),
+            InitError::Init(error) => ("", Some((error, [].as_slice()))),
+            InitError::PostInit(errors) => ("post-", errors.split_first()),
+            InitError::AlreadyInitialized => {
+                return write!(f, "The module registry has already been initialized");
+            }
         };
-        let (first, rest) = match self {
-            InitError::PreInit(errors) => errors.split_first(),
-            InitError::Init(error) => Some((error, [].as_slice())),
-            InitError::PostInit(errors) => errors.split_first(),
-        }
-        .unwrap_or_else(|| unreachable!("Error lists should not be empty"));
+        let (first, rest) =
+            errors.unwrap_or_else(|| unreachable!("Error lists should not be empty"));
         write!(f, "Error during module {phase}initialisation: {first}")?;
-        if rest.is_empty() {
+        if !rest.is_empty() {
             write!(f, " (and {} more...)", rest.len())?;
         }
         Ok(())
@@ -267,3 +306,52 @@ fn process_join_results<T, E: From<String>>(
         Err(errors)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static INSTANCES: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counter(usize);
+
+    impl Module for Counter {
+        type PreInit = ();
+
+        async fn pre_init() -> Result<Self::PreInit, module::PreInitError> {
+            Ok(())
+        }
+
+        type Dependencies = ();
+
+        async fn init(
+            _pre_init: Self::PreInit,
+            _dependencies: &mut Self::Dependencies,
+        ) -> Result<Self, module::InitError> {
+            Ok(Counter(INSTANCES.fetch_add(1, Ordering::Relaxed)))
+        }
+    }
+
+    #[test]
+    fn every_init_for_current_thread_creates_fresh_modules() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            RegistryBuilder::new()
+                .register_module::<Counter>()
+                .init_for_current_thread()
+                .await
+                .unwrap();
+            let first = Counter::global().0;
+
+            RegistryBuilder::new()
+                .register_module::<Counter>()
+                .init_for_current_thread()
+                .await
+                .unwrap();
+            assert_ne!(Counter::global().0, first);
+        });
+    }
+}
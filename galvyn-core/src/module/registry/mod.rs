@@ -4,6 +4,7 @@ use crate::module::registry::builder::RegistryBuilder;
 use crate::module::registry::module_set::LeakedModuleSet;
 use crate::module::Module;
 use std::any::Any;
+use std::cell::Cell;
 use std::sync::OnceLock;
 use tokio::task::JoinHandle;
 
@@ -30,14 +31,17 @@ impl Registry {
 
     #[track_caller]
     pub fn global() -> &'static Self {
-        let Some(global) = Self::raw_global().get() else {
+        let Some(global) = Self::try_global() else {
             panic!("The global registry has not been initialized yet.");
         };
         global
     }
 
+    /// Gets the registry initialized for the current thread or else the process-global one
+    ///
+    /// See [`RegistryBuilder::init_for_current_thread`].
     pub fn try_global() -> Option<&'static Self> {
-        Self::raw_global().get()
+        THREAD_LOCAL.get().or_else(|| Self::raw_global().get())
     }
 
     pub fn try_get<T: Module>(&self) -> Option<&T> {
@@ -49,3 +53,8 @@ impl Registry {
         &GLOBAL
     }
 }
+
+thread_local! {
+    /// The registry initialized by [`RegistryBuilder::init_for_current_thread`]
+    static THREAD_LOCAL: Cell<Option<&'static Registry>> = const { Cell::new(None) };
+}
@@ -1,15 +1,30 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::convert::Infallible;
+use std::fmt;
 use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::OnceLock;
 
 use axum::extract::Request;
+use axum::http::Method;
+use axum::middleware;
 use axum::response::IntoResponse;
 use axum::routing::Route;
 use axum::routing::Router;
+use regex::Regex;
+use schemars::Map;
+use serde::Serialize;
+use serde_json::Value;
 use tower::Layer;
 use tower::Service;
 
+use crate::handler::request_part::PathParameters;
 use crate::handler::GalvynHandler;
 use crate::handler::HandlerMeta;
+use crate::schema_generator::SchemaGenerator;
+use crate::stuff::content_type;
+use crate::stuff::request_observer;
 // use crate::{SwaggapiPage, PAGE_OF_EVERYTHING};
 
 /// An `GalvynRouter` combines several [`SwaggapiHandler`] under a common path.
@@ -20,7 +35,7 @@ use crate::handler::HandlerMeta;
 #[derive(Debug, Default)]
 pub struct GalvynRouter {
     /// The contained handlers
-    handlers: Vec<MutHandlerMeta>,
+    handlers: Vec<GalvynRoute>,
 
     /// The underlying axum router
     router: Router,
@@ -31,6 +46,16 @@ pub struct GalvynRouter {
     /// This is effectively remembers the argument actix' `Scope` was created with.
     /// Since `Router` doesn't take a path, this will always be empty for axum.
     path: String,
+
+    /// Security schemes protecting all handlers
+    ///
+    /// Changes have to be applied to already existing `handlers` manually
+    security: Vec<SecuritySchemeMetadata>,
+
+    /// Vendor extensions added to all handlers
+    ///
+    /// Changes have to be applied to already existing `handlers` manually
+    extensions: BTreeMap<&'static str, Value>,
     //
     // /// Changes have to be applied to already existing `handlers` manually
     // pages: Vec<&'static SwaggapiPage>,
@@ -61,12 +86,116 @@ impl GalvynRouter {
     //     Self::new().tag(tag)
     // }
 
+    /// Returns the routes registered so far
+    ///
+    /// This is mainly useful in tests to verify that a router contains the expected endpoints
+    /// without having to start a server.
+    pub fn routes(&self) -> &[GalvynRoute] {
+        &self.handlers
+    }
+
+    /// Checks every route's path parameters against its handler's extractors
+    ///
+    /// This catches routes with a `{param}` in their path but no [`Path`](axum::extract::Path) extractor
+    /// (or vice versa) and `Path` extractors whose fields don't match the path's parameters.
+    pub fn validate_routes(&self) -> Vec<RouteIssue> {
+        SchemaGenerator::employ(&mut Map::new(), |gen| {
+            let mut issues = Vec::new();
+            for route in &self.handlers {
+                let path_parameters = route_path_parameters(&route.path);
+                let extractors = route
+                    .request_parts
+                    .iter()
+                    .filter_map(|part| (part.path_parameters)(&mut *gen))
+                    .collect::<Vec<_>>();
+
+                let kind = match (path_parameters.is_empty(), extractors.as_slice()) {
+                    (true, []) => continue,
+                    (false, []) => RouteIssueKind::MissingPathExtractor {
+                        parameters: path_parameters,
+                    },
+                    (true, [_, ..]) => RouteIssueKind::UnexpectedPathExtractor,
+                    (false, extractors) => {
+                        let expected = path_parameters.iter().collect::<BTreeSet<_>>();
+                        let Some(extracted) = extractors.iter().find_map(|params| match params {
+                            PathParameters::Named(names) => {
+                                let actual = names.iter().collect::<BTreeSet<_>>();
+                                (actual != expected).then_some(names)
+                            }
+                            PathParameters::Unnamed => None,
+                        }) else {
+                            continue;
+                        };
+                        RouteIssueKind::MismatchedPathParameters {
+                            path: path_parameters,
+                            extractor: extracted.clone(),
+                        }
+                    }
+                };
+                issues.push(RouteIssue {
+                    method: route.method.clone(),
+                    path: route.path.clone(),
+                    handler: route.ident,
+                    kind,
+                });
+            }
+            issues
+        })
+    }
+
     /// Add a handler to the router
     pub fn handler(mut self, handler: impl GalvynHandler) -> Self {
-        self.push_handler(MutHandlerMeta::new(handler.meta()));
-        self.router = self
-            .router
-            .route(&handler.meta().path, handler.method_router());
+        let meta = handler.meta();
+        let mut method_router = handler.method_router();
+        if !meta.accept.is_empty() {
+            method_router = method_router.layer(middleware::from_fn_with_state(
+                meta.accept,
+                content_type::middleware,
+            ));
+        }
+        self.router = self.router.route(
+            meta.path,
+            method_router.layer(middleware::from_fn_with_state(
+                Arc::new(meta.clone()),
+                request_observer::middleware,
+            )),
+        );
+        self.push_handler(GalvynRoute::new(meta));
+        self
+    }
+
+    /// Marks all of this router's handlers as requiring a security scheme
+    ///
+    /// This applies to the handlers added before and after calling this method.
+    /// Calling it multiple times with different schemes means each of them is accepted.
+    pub fn security(mut self, scheme: SecuritySchemeMetadata) -> Self {
+        for handler in &mut self.handlers {
+            handler.security.push(scheme.clone());
+        }
+        self.security.push(scheme);
+        self
+    }
+
+    /// Adds a vendor extension (i.e. `x-internal`) to all of this router's handlers' operations
+    ///
+    /// This applies to the handlers added before and after calling this method.
+    /// Extensions set on a nested or merged router take precedence.
+    ///
+    /// # Panics
+    /// If `name` doesn't start with `x-` or `value` can't be serialized
+    pub fn extension(mut self, name: &'static str, value: impl Serialize) -> Self {
+        assert!(
+            name.starts_with("x-"),
+            "Vendor extensions have to start with `x-`, got `{name}`"
+        );
+        let value = serde_json::to_value(value).expect("The extension should be serializable");
+        for handler in &mut self.handlers {
+            handler
+                .extensions
+                .entry(name)
+                .or_insert_with(|| value.clone());
+        }
+        self.extensions.insert(name, value);
         self
     }
 
@@ -88,11 +217,18 @@ impl GalvynRouter {
     //     self
     // }
 
-    /// Adds a [`MutHandlerMeta`] after adding this router's `path`, `tags` and `pages` to it
-    fn push_handler(&mut self, mut handler: MutHandlerMeta) {
+    /// Adds a [`GalvynRoute`] after adding this router's `path`, `tags` and `pages` to it
+    fn push_handler(&mut self, mut handler: GalvynRoute) {
         if !self.path.is_empty() {
             handler.path = format!("{}{}", self.path, handler.path);
         }
+        handler.security.extend(self.security.iter().cloned());
+        for (name, value) in &self.extensions {
+            handler
+                .extensions
+                .entry(name)
+                .or_insert_with(|| value.clone());
+        }
         // handler.tags.extend(self.tags.iter().copied());
         // handler.pages.extend(self.pages.iter().copied());
         self.handlers.push(handler);
@@ -191,30 +327,200 @@ impl From<GalvynRouter> for Router {
     }
 }
 
-/// A wrapped [`HandlerMeta`] used inside [`GalvynRouter`] to allow modifications.
-#[derive(Debug)]
-pub(crate) struct MutHandlerMeta {
+/// Extracts the names of the parameters in a route's path
+///
+/// (i.e. `["user", "post"]` for `/users/{user}/posts/{post}`)
+fn route_path_parameters(path: &str) -> Vec<String> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+
+    let regex = RE.get_or_init(|| Regex::new(r"\{\*?([^}]*)}").unwrap());
+    regex
+        .captures_iter(path)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+/// A problem with a route found by [`GalvynRouter::validate_routes`]
+#[derive(Clone, Debug)]
+pub struct RouteIssue {
+    /// The route's http method
+    pub method: Method,
+
+    /// The route's path
+    pub path: String,
+
+    /// The identifier of the route's handler
+    pub handler: &'static str,
+
+    /// The actual problem
+    pub kind: RouteIssueKind,
+}
+
+/// The kinds of [`RouteIssue`]s
+#[derive(Clone, Debug)]
+pub enum RouteIssueKind {
+    /// The path contains parameters, but the handler doesn't extract them
+    MissingPathExtractor {
+        /// The parameters contained in the path
+        parameters: Vec<String>,
+    },
+
+    /// The handler extracts path parameters, but the path doesn't contain any
+    UnexpectedPathExtractor,
+
+    /// The handler's extractor expects other parameters than the path contains
+    MismatchedPathParameters {
+        /// The parameters contained in the path
+        path: Vec<String>,
+
+        /// The parameters expected by the extractor
+        extractor: Vec<String>,
+    },
+}
+
+impl fmt::Display for RouteIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            method,
+            path,
+            handler,
+            kind,
+        } = self;
+        write!(f, "{method} {path} ({handler}): ")?;
+        match kind {
+            RouteIssueKind::MissingPathExtractor { parameters } => write!(
+                f,
+                "the path contains the parameters {parameters:?} but the handler has no `Path` extractor"
+            ),
+            RouteIssueKind::UnexpectedPathExtractor => write!(
+                f,
+                "the handler has a `Path` extractor but the path contains no parameters"
+            ),
+            RouteIssueKind::MismatchedPathParameters { path, extractor } => write!(
+                f,
+                "the path contains the parameters {path:?} but the handler's `Path` extractor expects {extractor:?}"
+            ),
+        }
+    }
+}
+
+/// A route registered in a [`GalvynRouter`]
+///
+/// It wraps the handler's [`HandlerMeta`] and stores the modifications applied by the router
+/// (for example the path prefix added by [`GalvynRouter::nest`]).
+#[derive(Clone, Debug)]
+pub struct GalvynRoute {
     /// The original unmodified [`HandlerMeta`]
     pub original: HandlerMeta,
 
     /// The handler's modified path
     pub path: String,
+
+    /// The security schemes added by [`GalvynRouter::security`]
+    ///
+    /// Any one of them is sufficient to access the route.
+    /// If it is empty, the route doesn't require authentication.
+    pub security: Vec<SecuritySchemeMetadata>,
+
+    /// The vendor extensions added by [`GalvynRouter::extension`]
+    pub extensions: BTreeMap<&'static str, Value>,
 }
-impl MutHandlerMeta {
-    /// Constructs a new `MutHandlerMeta`
-    pub fn new(original: HandlerMeta) -> Self {
+impl GalvynRoute {
+    /// Constructs a new `GalvynRoute`
+    pub(crate) fn new(original: HandlerMeta) -> Self {
         Self {
             path: original.path.to_string(),
+            security: Vec::new(),
+            extensions: BTreeMap::new(),
             // tags: PtrSet::from_iter(original.tags.iter().copied()),
             // pages: PtrSet::new(),
             original,
         }
     }
 }
-impl Deref for MutHandlerMeta {
+impl Deref for GalvynRoute {
     type Target = HandlerMeta;
 
     fn deref(&self) -> &Self::Target {
         &self.original
     }
 }
+
+/// A security scheme protecting a route
+///
+/// Routes are marked using [`GalvynRouter::security`].
+/// The schemes are meant to populate the OpenAPI document's `components.securitySchemes`
+/// and the operations' `security` requirements.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecuritySchemeMetadata {
+    /// The name identifying the scheme in `components.securitySchemes`
+    pub name: &'static str,
+
+    /// How the client transmits its credentials
+    pub kind: SecuritySchemeKind,
+
+    /// A description of the scheme
+    pub description: Option<&'static str>,
+}
+
+/// The ways a client transmits its credentials
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SecuritySchemeKind {
+    /// A token sent in a cookie (for example a session id)
+    Cookie {
+        /// The cookie's name
+        cookie: &'static str,
+    },
+
+    /// A token sent in a header (for example an api key)
+    Header {
+        /// The header's name
+        header: &'static str,
+    },
+
+    /// A token sent in the `Authorization: Bearer <token>` header
+    Bearer {
+        /// A hint how the token is formatted (for example `JWT`)
+        bearer_format: Option<&'static str>,
+    },
+}
+
+impl SecuritySchemeMetadata {
+    /// The session cookie set by [`session::layer`](crate::session::layer)
+    pub const fn session_cookie() -> Self {
+        Self::cookie("session", "id")
+    }
+
+    /// A token sent in a cookie
+    pub const fn cookie(name: &'static str, cookie: &'static str) -> Self {
+        Self {
+            name,
+            kind: SecuritySchemeKind::Cookie { cookie },
+            description: None,
+        }
+    }
+
+    /// A token sent in a header
+    pub const fn header(name: &'static str, header: &'static str) -> Self {
+        Self {
+            name,
+            kind: SecuritySchemeKind::Header { header },
+            description: None,
+        }
+    }
+
+    /// A token sent in the `Authorization: Bearer <token>` header
+    pub const fn bearer(name: &'static str, bearer_format: Option<&'static str>) -> Self {
+        Self {
+            name,
+            kind: SecuritySchemeKind::Bearer { bearer_format },
+            description: None,
+        }
+    }
+
+    /// Sets the scheme's description
+    pub const fn description(mut self, description: &'static str) -> Self {
+        self.description = Some(description);
+        self
+    }
+}
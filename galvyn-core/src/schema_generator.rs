@@ -1,11 +1,47 @@
 use std::mem;
+use std::sync::OnceLock;
 
 use schemars::gen::SchemaGenerator as InnerGenerator;
 use schemars::gen::SchemaSettings;
 use schemars::schema::{ObjectValidation, Schema, SchemaObject};
+use schemars::visit::{self, Visitor};
 use schemars::JsonSchema;
 use schemars::Map;
 
+/// How the names of schemas colliding with already generated ones are resolved
+///
+/// Schemas are named after their type without its module (i.e. `Response`),
+/// so distinct types can end up with the same name.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SchemaNaming {
+    /// Append a number to the later schema's name (i.e. `Response2`)
+    #[default]
+    SuffixOnConflict,
+
+    /// Panic when the documentation is generated
+    ///
+    /// Use this to make sure every schema has a unique name,
+    /// for example by renaming types with `#[schemars(rename = "...")]`.
+    PanicOnConflict,
+}
+
+static SCHEMA_NAMING: OnceLock<SchemaNaming> = OnceLock::new();
+
+impl SchemaNaming {
+    /// Sets the strategy all schemas should be named with
+    ///
+    /// This can only be set once and should happen before any schemas are generated.
+    /// Returns the rejected strategy if it has already been set.
+    pub fn set_global(self) -> Result<(), SchemaNaming> {
+        SCHEMA_NAMING.set(self)
+    }
+
+    /// Gets the strategy all schemas are named with
+    pub fn global() -> SchemaNaming {
+        SCHEMA_NAMING.get().copied().unwrap_or_default()
+    }
+}
+
 /// State for generating schemas from types implementing [`JsonSchema`]
 ///
 /// If you require the underlying [`SchemaGenerator` from `schemars`](schemars::gen::SchemaGenerator),
@@ -27,7 +63,7 @@ impl SchemaGenerator {
     /// This might do nothing but return a reference to the schema
     /// already added to the generator previously.
     pub fn generate<T: JsonSchema>(&mut self) -> Schema {
-        self.0.subschema_for::<T>()
+        self.isolated(|gen| gen.subschema_for::<T>())
     }
 
     /// Generate an openapi schema for the type `T`
@@ -39,7 +75,110 @@ impl SchemaGenerator {
     /// outlined in `JsonSchema`'s docs.
     /// Namely, [`JsonSchema::json_schema`] **should not** return a `$ref` schema.
     pub fn generate_refless<T: JsonSchema>(&mut self) -> Schema {
-        T::json_schema(&mut self.0)
+        self.isolated(|gen| T::json_schema(gen))
+    }
+
+    /// Runs `func` with an empty generator and merges its definitions into this one's
+    ///
+    /// `schemars` only avoids name collisions between the schemas it generated itself.
+    /// Since the generator is recreated by [`SchemaGenerator::employ`],
+    /// collisions with the definitions generated previously are resolved here according to [`SchemaNaming`].
+    fn isolated(&mut self, func: impl FnOnce(&mut InnerGenerator) -> Schema) -> Schema {
+        let mut gen = InnerGenerator::new(self.0.settings().clone());
+        let mut schema = func(&mut gen);
+        let mut new_definitions = gen.take_definitions();
+
+        let definitions_path = self.0.settings().definitions_path.clone();
+        let definitions = self.0.definitions_mut();
+
+        let mut dependencies = Map::<String, Vec<String>>::new();
+        for (name, new) in new_definitions.iter_mut() {
+            let mut collector = CollectReferences {
+                definitions_path: &definitions_path,
+                names: Vec::new(),
+            };
+            collector.visit_schema(new);
+            dependencies.insert(name.clone(), collector.names);
+        }
+
+        // Name the definitions referenced by others first,
+        // so every candidate is compared with the references its definition will end up with.
+        let mut renamer = RenameReferences {
+            definitions_path,
+            renames: Map::new(),
+        };
+        let mut pending = new_definitions.keys().collect::<Vec<_>>();
+        while !pending.is_empty() {
+            let next = pending
+                .iter()
+                .position(|name| {
+                    dependencies[*name]
+                        .iter()
+                        .all(|dependency| dependency == *name || !pending.contains(&dependency))
+                })
+                // Definitions referencing each other in a cycle can't wait for one another
+                .unwrap_or(0);
+            let name = pending.remove(next);
+            let new = &new_definitions[name];
+
+            let renamed = (1..)
+                .map(|i| match i {
+                    1 => name.clone(),
+                    i => format!("{name}{i}"),
+                })
+                .find(|candidate| {
+                    if candidate != name
+                        && (new_definitions.contains_key(candidate)
+                            || renamer.renames.values().any(|taken| taken == candidate))
+                    {
+                        return false;
+                    }
+                    definitions.get(candidate).is_none_or(|existing| {
+                        renamer.renames.insert(name.clone(), candidate.clone());
+                        let mut new = new.clone();
+                        renamer.visit_schema(&mut new);
+                        *existing == new
+                    })
+                })
+                .unwrap_or_default();
+
+            if renamed != *name && SchemaNaming::global() == SchemaNaming::PanicOnConflict {
+                panic!("Two different schemas are named `{name}`");
+            }
+            renamer.renames.insert(name.clone(), renamed);
+        }
+
+        // A definition in a cycle has been compared before the rest of the cycle was named.
+        // If it differs from the existing one after all, it gets a name which isn't used yet.
+        loop {
+            let conflict = new_definitions.iter().find_map(|(name, new)| {
+                let existing = definitions.get(&renamer.renames[name])?;
+                let mut new = new.clone();
+                renamer.visit_schema(&mut new);
+                (*existing != new).then(|| name.clone())
+            });
+            let Some(name) = conflict else {
+                break;
+            };
+
+            let renamed = (2..)
+                .map(|i| format!("{name}{i}"))
+                .find(|candidate| {
+                    !definitions.contains_key(candidate)
+                        && !new_definitions.contains_key(candidate)
+                        && !renamer.renames.values().any(|taken| taken == candidate)
+                })
+                .unwrap_or_default();
+            renamer.renames.insert(name, renamed);
+        }
+
+        for (name, mut new) in new_definitions {
+            renamer.visit_schema(&mut new);
+            definitions.insert(renamer.renames[&name].clone(), new);
+        }
+        renamer.visit_schema(&mut schema);
+
+        schema
     }
 
     /// Generate an openapi schema of `"type": "object"`
@@ -94,3 +233,93 @@ impl SchemaGenerator {
         output
     }
 }
+
+/// Replaces the references to renamed schemas
+struct RenameReferences {
+    definitions_path: String,
+    /// Maps the schemas' old names to their new ones
+    renames: Map<String, String>,
+}
+
+impl Visitor for RenameReferences {
+    fn visit_schema_object(&mut self, schema: &mut SchemaObject) {
+        let renamed = schema
+            .reference
+            .as_deref()
+            .and_then(|reference| reference.strip_prefix(self.definitions_path.as_str()))
+            .and_then(|name| self.renames.get(name));
+        if let Some(renamed) = renamed {
+            schema.reference = Some(format!("{}{renamed}", self.definitions_path));
+        }
+        visit::visit_schema_object(self, schema);
+    }
+}
+
+/// Collects the names of all referenced schemas
+struct CollectReferences<'a> {
+    definitions_path: &'a str,
+    names: Vec<String>,
+}
+
+impl Visitor for CollectReferences<'_> {
+    fn visit_schema_object(&mut self, schema: &mut SchemaObject) {
+        let name = schema
+            .reference
+            .as_deref()
+            .and_then(|reference| reference.strip_prefix(self.definitions_path));
+        if let Some(name) = name {
+            self.names.push(name.to_string());
+        }
+        visit::visit_schema_object(self, schema);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[allow(dead_code)]
+    mod first {
+        use schemars::JsonSchema;
+
+        #[derive(JsonSchema)]
+        pub struct Container {
+            pub item: Item,
+        }
+
+        #[derive(JsonSchema)]
+        pub struct Item {
+            pub id: u32,
+        }
+    }
+
+    #[allow(dead_code)]
+    mod second {
+        use schemars::JsonSchema;
+
+        #[derive(JsonSchema)]
+        pub struct Container {
+            pub item: Item,
+            pub count: u32,
+        }
+
+        #[derive(JsonSchema)]
+        pub struct Item {
+            pub name: String,
+        }
+    }
+
+    #[test]
+    fn a_renamed_schema_keeps_its_name_when_generated_again() {
+        let mut definitions = Map::new();
+        SchemaGenerator::employ(&mut definitions, |gen| {
+            gen.generate::<first::Container>();
+            let schema = gen.generate::<second::Container>();
+            assert_eq!(gen.generate::<second::Container>(), schema);
+        });
+
+        let mut names = definitions.keys().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["Container", "Container2", "Item", "Item2"]);
+    }
+}
@@ -2,8 +2,20 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use std::fmt::Formatter;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use async_trait::async_trait;
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::header;
+use axum::http::HeaderValue;
+use axum::http::Method;
+use axum::http::StatusCode;
+use axum::middleware;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use axum::Router;
 use rorm::and;
 use rorm::delete;
 use rorm::fields::types::Json;
@@ -31,9 +43,125 @@ use tracing::instrument;
 use crate::Module;
 
 pub fn layer() -> SessionManagerLayer<RormStore> {
-    SessionManagerLayer::new(RormStore::new(Database::global().clone()))
-        .with_expiry(Expiry::OnInactivity(Duration::hours(24)))
-        .with_same_site(SameSite::Lax)
+    SessionSetup::default().layer()
+}
+
+/// Configures how sessions are stored in the client's cookies
+///
+/// The default is a `SameSite=Lax` cookie which is suited for frontends served from the same site as the api.
+#[derive(Clone, Debug)]
+pub struct SessionSetup {
+    same_site: SameSite,
+    secure: bool,
+    expiry: Expiry,
+    /// Origins allowed to make state changing requests, if CSRF protection is enabled
+    csrf_origins: Option<Arc<[HeaderValue]>>,
+}
+
+impl Default for SessionSetup {
+    fn default() -> Self {
+        Self {
+            same_site: SameSite::Lax,
+            secure: false,
+            expiry: Expiry::OnInactivity(Duration::hours(24)),
+            csrf_origins: None,
+        }
+    }
+}
+
+impl SessionSetup {
+    /// Preset for frontends served from a different site than the api
+    ///
+    /// The session cookie is sent as `SameSite=None; Secure` which browsers only accept over HTTPS.
+    /// (`http://localhost` is exempt from this in most browsers.)
+    ///
+    /// Since the browser will attach such a cookie to requests initiated by any site,
+    /// this preset also enables a CSRF protection:
+    /// Requests with a method other than `GET`, `HEAD` and `OPTIONS` are rejected with `403 Forbidden`
+    /// unless their `Origin` header matches one of the `allowed_origins` (for example `https://app.example.com`).
+    ///
+    /// The frontend's origin has to be allowed by your CORS configuration as well,
+    /// including `Access-Control-Allow-Credentials: true`.
+    /// The CORS middleware should be the outermost layer,
+    /// because preflight requests don't carry cookies and are not checked by the CSRF protection.
+    ///
+    /// # Panics
+    /// If one of the `allowed_origins` is not a valid header value.
+    pub fn cross_site<I>(allowed_origins: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Self {
+            same_site: SameSite::None,
+            secure: true,
+            csrf_origins: Some(
+                allowed_origins
+                    .into_iter()
+                    .map(|origin| {
+                        let origin = origin.as_ref();
+                        HeaderValue::from_str(origin).unwrap_or_else(|_| {
+                            panic!("The allowed origin `{origin}` is not a valid header value")
+                        })
+                    })
+                    .collect(),
+            ),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the session's expiry
+    pub fn expiry(mut self, expiry: Expiry) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    /// Constructs the layer managing the session
+    ///
+    /// This does not include the CSRF protection, use [`SessionSetup::apply`] to add both.
+    pub fn layer(&self) -> SessionManagerLayer<RormStore> {
+        SessionManagerLayer::new(RormStore::new(Database::global().clone()))
+            .with_expiry(self.expiry)
+            .with_same_site(self.same_site)
+            .with_secure(self.secure)
+    }
+
+    /// Adds the session layer and (if enabled) the CSRF protection to a router
+    pub fn apply(&self, router: Router) -> Router {
+        let router = router.layer(self.layer());
+        match &self.csrf_origins {
+            Some(origins) => router.layer(middleware::from_fn_with_state(
+                origins.clone(),
+                csrf_middleware,
+            )),
+            None => router,
+        }
+    }
+}
+
+/// Rejects state changing requests whose `Origin` is not allowed
+async fn csrf_middleware(
+    State(allowed_origins): State<Arc<[HeaderValue]>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if matches!(
+        *request.method(),
+        Method::GET | Method::HEAD | Method::OPTIONS
+    ) {
+        return next.run(request).await;
+    }
+
+    let allowed = request
+        .headers()
+        .get(header::ORIGIN)
+        .is_some_and(|origin| allowed_origins.contains(origin));
+    if !allowed {
+        debug!("Rejected cross-site request due to its origin");
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    next.run(request).await
 }
 
 #[derive(Model)]
@@ -54,6 +182,32 @@ impl RormStore {
     pub fn new(db: Database) -> Self {
         Self { db }
     }
+
+    /// Checks whether a session exists and hasn't expired yet
+    pub async fn is_active(&self, session_id: &str) -> Result<bool, rorm::Error> {
+        let db = &self.db;
+
+        let session = query!(db, GalvynSession)
+            .condition(and!(
+                GalvynSession.id.equals(session_id),
+                GalvynSession
+                    .expires_at
+                    .greater_than(OffsetDateTime::now_utc())
+            ))
+            .optional()
+            .await?;
+        Ok(session.is_some())
+    }
+
+    /// Deletes a session, logging out the client using it
+    pub async fn delete_by_id(&self, session_id: &str) -> Result<(), rorm::Error> {
+        let db = &self.db;
+
+        delete!(db, GalvynSession)
+            .condition(GalvynSession.id.equals(session_id))
+            .await?;
+        Ok(())
+    }
 }
 
 impl Debug for RormStore {
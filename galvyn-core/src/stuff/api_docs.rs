@@ -0,0 +1,130 @@
+//! HTML pages rendering an OpenAPI document as human-readable documentation
+//!
+//! The page is a small HTML shell loading [Redoc](https://github.com/Redocly/redoc)
+//! or [Scalar](https://github.com/scalar/scalar) which fetch and render the document from its url.
+//! Serve it from a handler of your choice:
+//!
+//! ```rust,ignore
+//! use galvyn::core::stuff::api_docs::ApiDocsPage;
+//!
+//! #[get("/docs")]
+//! async fn docs() -> Html<String> {
+//!     ApiDocsPage::scalar("/api/openapi.json")
+//!         .title("My API")
+//!         .render()
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use axum::response::Html;
+
+/// The default script rendering a [`ApiDocsRenderer::Redoc`] page
+const REDOC_SCRIPT: &str = "https://cdn.redoc.ly/redoc/latest/bundles/redoc.standalone.js";
+
+/// The default script rendering a [`ApiDocsRenderer::Scalar`] page
+const SCALAR_SCRIPT: &str = "https://cdn.jsdelivr.net/npm/@scalar/api-reference";
+
+/// The javascript libraries rendering an OpenAPI document
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ApiDocsRenderer {
+    /// [Redoc](https://github.com/Redocly/redoc)
+    Redoc,
+
+    /// [Scalar](https://github.com/scalar/scalar)
+    Scalar,
+}
+
+/// A documentation page for an OpenAPI document
+#[derive(Clone, Debug)]
+pub struct ApiDocsPage {
+    renderer: ApiDocsRenderer,
+    spec_url: String,
+    title: String,
+    script_url: Option<String>,
+}
+
+impl ApiDocsPage {
+    /// Constructs a page rendering the document at `spec_url` using Redoc
+    pub fn redoc(spec_url: impl Into<String>) -> Self {
+        Self::new(ApiDocsRenderer::Redoc, spec_url)
+    }
+
+    /// Constructs a page rendering the document at `spec_url` using Scalar
+    pub fn scalar(spec_url: impl Into<String>) -> Self {
+        Self::new(ApiDocsRenderer::Scalar, spec_url)
+    }
+
+    /// Constructs a page rendering the document at `spec_url`
+    pub fn new(renderer: ApiDocsRenderer, spec_url: impl Into<String>) -> Self {
+        Self {
+            renderer,
+            spec_url: spec_url.into(),
+            title: "API Documentation".to_string(),
+            script_url: None,
+        }
+    }
+
+    /// Sets the page's title
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Loads the renderer's script from another url
+    ///
+    /// By default, it is loaded from a public CDN.
+    /// Use this to serve it yourself, for example if your content security policy forbids the CDN.
+    pub fn script_url(mut self, script_url: impl Into<String>) -> Self {
+        self.script_url = Some(script_url.into());
+        self
+    }
+
+    /// Renders the page's HTML
+    pub fn render(&self) -> Html<String> {
+        let script_url = self.script_url.as_deref().unwrap_or(match self.renderer {
+            ApiDocsRenderer::Redoc => REDOC_SCRIPT,
+            ApiDocsRenderer::Scalar => SCALAR_SCRIPT,
+        });
+
+        let mut html = String::new();
+        html.push_str("<!doctype html>\n<html>\n<head>\n");
+        html.push_str("<meta charset=\"utf-8\" />\n");
+        html.push_str(
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\n",
+        );
+        let _ = writeln!(html, "<title>{}</title>", escape(&self.title));
+        html.push_str("</head>\n<body>\n");
+        let _ = match self.renderer {
+            ApiDocsRenderer::Redoc => writeln!(
+                html,
+                "<redoc spec-url=\"{}\"></redoc>",
+                escape(&self.spec_url)
+            ),
+            ApiDocsRenderer::Scalar => writeln!(
+                html,
+                "<script id=\"api-reference\" data-url=\"{}\"></script>",
+                escape(&self.spec_url)
+            ),
+        };
+        let _ = writeln!(html, "<script src=\"{}\"></script>", escape(script_url));
+        html.push_str("</body>\n</html>\n");
+        Html(html)
+    }
+}
+
+/// Escapes a string to be used in HTML text and quoted attributes
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for char in value.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
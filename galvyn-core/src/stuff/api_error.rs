@@ -2,18 +2,28 @@
 //! that are returned from handlers
 
 use std::error::Error;
+use std::fmt;
 use std::ops::Deref;
 use std::panic::Location;
+use std::sync::OnceLock;
 
 use crate::handler::response_body::{ResponseBody, ShouldBeResponseBody};
 
 use crate::schema_generator::SchemaGenerator;
 
+use axum::http::header;
+use axum::http::HeaderMap;
+use axum::http::HeaderName;
+use axum::http::HeaderValue;
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::response::Response;
+use axum::Json;
 use mime::Mime;
 use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tracing::error;
 use tracing::info;
 
 /// A type alias that includes the ApiError
@@ -23,12 +33,98 @@ pub struct ApiError {
     kind: ApiErrorKind,
     location: Option<&'static Location<'static>>,
     source: Option<DynError>,
+    headers: HeaderMap,
 }
 
 enum ApiErrorKind {
-    Client,
+    Client(StatusCode),
     Server,
 }
+/// The format errors are rendered in
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum ErrorFormat {
+    /// Respond with the status code only and an empty body
+    #[default]
+    StatusOnly,
+
+    /// Respond with an RFC 7807 problem details object (`application/problem+json`)
+    ///
+    /// See [`ProblemDetails`].
+    ProblemJson,
+}
+
+static ERROR_FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
+
+impl ErrorFormat {
+    /// Sets the format all errors should be rendered in
+    ///
+    /// This can only be set once and should happen before any routes are served.
+    /// Returns the rejected format if it has already been set.
+    pub fn set_global(self) -> Result<(), ErrorFormat> {
+        ERROR_FORMAT.set(self)
+    }
+
+    /// Gets the format all errors are rendered in
+    pub fn global() -> ErrorFormat {
+        ERROR_FORMAT.get().copied().unwrap_or_default()
+    }
+}
+
+/// An RFC 7807 problem details object
+///
+/// Responded with as `application/problem+json` when using [`ErrorFormat::ProblemJson`].
+#[derive(Serialize, JsonSchema, Clone, Debug)]
+pub struct ProblemDetails {
+    /// A URI reference identifying the problem type
+    #[serde(rename = "type")]
+    pub r#type: String,
+
+    /// A short, human-readable summary of the problem type
+    pub title: String,
+
+    /// The http status code
+    pub status: u16,
+
+    /// A human-readable explanation specific to this occurrence of the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// A URI reference identifying this specific occurrence of the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+}
+
+impl ProblemDetails {
+    /// Constructs a problem details object without a specific type
+    pub fn new(status_code: StatusCode, detail: Option<String>) -> Self {
+        Self {
+            r#type: "about:blank".to_string(),
+            title: status_code
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+            status: status_code.as_u16(),
+            detail,
+            instance: None,
+        }
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status_code =
+            StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let mut response = (status_code, Json(self)).into_response();
+        response.headers_mut().insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(APPLICATION_PROBLEM_JSON),
+        );
+        response
+    }
+}
+
+const APPLICATION_PROBLEM_JSON: &str = "application/problem+json";
+
 #[derive(Debug)]
 pub struct DynError(Box<dyn Error + Send + Sync + 'static>);
 impl<E> From<E> for DynError
@@ -41,7 +137,21 @@ where
 }
 impl IntoResponse for DynError {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, self.0.to_string()).into_response()
+        match self.0.downcast::<ApiError>() {
+            Ok(api_error) => api_error.into_response(),
+            Err(error) => match ErrorFormat::global() {
+                ErrorFormat::StatusOnly => {
+                    (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response()
+                }
+                ErrorFormat::ProblemJson => ApiError {
+                    kind: ApiErrorKind::Server,
+                    location: None,
+                    source: Some(DynError(error)),
+                    headers: HeaderMap::new(),
+                }
+                .into_response(),
+            },
+        }
     }
 }
 impl Deref for DynError {
@@ -56,7 +166,52 @@ impl ApiError {
     /// Constructs a new `ApiError` which the cliebt is to be blamed for
     #[track_caller]
     pub fn client_error(error: impl Into<DynError>) -> Self {
-        Self::new(error.into(), ApiErrorKind::Client)
+        Self::new(error.into(), ApiErrorKind::Client(StatusCode::BAD_REQUEST))
+    }
+
+    /// Constructs a new `ApiError` for a client which hasn't authenticated itself
+    #[track_caller]
+    pub fn unauthorized(error: impl Into<DynError>) -> Self {
+        Self::new(error.into(), ApiErrorKind::Client(StatusCode::UNAUTHORIZED))
+    }
+
+    /// Constructs a new `ApiError` for a client which isn't allowed to perform the request
+    #[track_caller]
+    pub fn forbidden(error: impl Into<DynError>) -> Self {
+        Self::new(error.into(), ApiErrorKind::Client(StatusCode::FORBIDDEN))
+    }
+
+    /// Constructs a new `ApiError` for a resource which doesn't exist
+    #[track_caller]
+    pub fn not_found(error: impl Into<DynError>) -> Self {
+        Self::new(error.into(), ApiErrorKind::Client(StatusCode::NOT_FOUND))
+    }
+
+    /// Constructs a new `ApiError` for a request whose precondition (like `If-Match`) failed
+    #[track_caller]
+    pub fn precondition_failed(error: impl Into<DynError>) -> Self {
+        Self::new(
+            error.into(),
+            ApiErrorKind::Client(StatusCode::PRECONDITION_FAILED),
+        )
+    }
+
+    /// Constructs a new `ApiError` for a request body of an unsupported content type
+    #[track_caller]
+    pub fn unsupported_media_type(error: impl Into<DynError>) -> Self {
+        Self::new(
+            error.into(),
+            ApiErrorKind::Client(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        )
+    }
+
+    /// Constructs a new `ApiError` for a client which has sent too many requests
+    #[track_caller]
+    pub fn too_many_requests(error: impl Into<DynError>) -> Self {
+        Self::new(
+            error.into(),
+            ApiErrorKind::Client(StatusCode::TOO_MANY_REQUESTS),
+        )
     }
 
     /// Constructs a new `ApiError` which the server is to be blamed for
@@ -71,31 +226,126 @@ impl ApiError {
             kind,
             location: Some(Location::caller()),
             source: Some(source),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Adds a header to the error's response (for example `Retry-After`)
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// The status code this error will be responded with
+    pub fn status_code(&self) -> StatusCode {
+        match self.kind {
+            ApiErrorKind::Client(status_code) => status_code,
+            ApiErrorKind::Server => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl fmt::Debug for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiError")
+            .field("status_code", &self.status_code())
+            .field("location", &self.location)
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}", source.0),
+            None => write!(f, "{}", self.status_code()),
         }
     }
 }
 
+impl Error for ApiError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|source| &*source.0 as &(dyn Error + 'static))
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let status_code = match self.kind {
-            ApiErrorKind::Client => StatusCode::BAD_REQUEST,
-            ApiErrorKind::Server => StatusCode::INTERNAL_SERVER_ERROR,
+        let status_code = self.status_code();
+        match self.kind {
+            ApiErrorKind::Client(_) => info!(
+                error.display = self.source.as_deref().map(tracing::field::display),
+                error.debug = self.source.as_deref().map(tracing::field::debug),
+                error.file = self.location.map(Location::file),
+                error.line = self.location.map(Location::line),
+                error.column = self.location.map(Location::column),
+                error.status = status_code.as_u16(),
+                "Client error",
+            ),
+            ApiErrorKind::Server => error!(
+                error.display = self.source.as_deref().map(tracing::field::display),
+                error.debug = self.source.as_deref().map(tracing::field::debug),
+                error.file = self.location.map(Location::file),
+                error.line = self.location.map(Location::line),
+                error.column = self.location.map(Location::column),
+                "Internal server error",
+            ),
+        }
+        let mut response = match ErrorFormat::global() {
+            ErrorFormat::StatusOnly => status_code.into_response(),
+            ErrorFormat::ProblemJson => {
+                // Don't leak internal details to the client
+                let detail = match self.kind {
+                    ApiErrorKind::Client(_) => self.source.map(|source| source.to_string()),
+                    ApiErrorKind::Server => None,
+                };
+                ProblemDetails::new(status_code, detail).into_response()
+            }
         };
-        info!(
-            error.display = self.source.as_deref().map(tracing::field::display),
-            error.debug = self.source.as_deref().map(tracing::field::debug),
-            error.file = self.location.map(Location::file),
-            error.line = self.location.map(Location::line),
-            error.column = self.location.map(Location::column),
-            "Internal server error",
-        );
-        status_code.into_response()
+        response.headers_mut().extend(self.headers);
+        response
+    }
+}
+
+impl ShouldBeResponseBody for DynError {}
+impl ResponseBody for DynError {
+    fn body(gen: &mut SchemaGenerator) -> Vec<(StatusCode, Option<(Mime, Option<Schema>)>)> {
+        // `DynError` is rendered like the `ApiError` it contains
+        ApiError::body(gen)
     }
 }
 
+/// The status codes an `ApiError` can be responded with (see its constructors)
+const STATUS_CODES: [StatusCode; 8] = [
+    StatusCode::BAD_REQUEST,
+    StatusCode::UNAUTHORIZED,
+    StatusCode::FORBIDDEN,
+    StatusCode::NOT_FOUND,
+    StatusCode::PRECONDITION_FAILED,
+    StatusCode::UNSUPPORTED_MEDIA_TYPE,
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::INTERNAL_SERVER_ERROR,
+];
+
 impl ShouldBeResponseBody for ApiError {}
 impl ResponseBody for ApiError {
-    fn body(_gen: &mut SchemaGenerator) -> Vec<(StatusCode, Option<(Mime, Option<Schema>)>)> {
-        todo!()
+    fn body(gen: &mut SchemaGenerator) -> Vec<(StatusCode, Option<(Mime, Option<Schema>)>)> {
+        match ErrorFormat::global() {
+            ErrorFormat::StatusOnly => STATUS_CODES
+                .into_iter()
+                .map(|status_code| (status_code, None))
+                .collect(),
+            ErrorFormat::ProblemJson => {
+                let mime: Mime = APPLICATION_PROBLEM_JSON.parse().unwrap();
+                let schema = gen.generate::<ProblemDetails>();
+                STATUS_CODES
+                    .into_iter()
+                    .map(|status_code| (status_code, Some((mime.clone(), Some(schema.clone())))))
+                    .collect()
+            }
+        }
     }
 }
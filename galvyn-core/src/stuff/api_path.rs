@@ -0,0 +1,75 @@
+//! Path parameters rejected with an [`ApiError`] instead of axum's default rejection
+
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use axum::extract::rejection::PathRejection;
+use axum::extract::FromRequestParts;
+use axum::extract::Path;
+use axum::http::request::Parts;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+
+use crate::handler::request_part::PathParameters;
+use crate::handler::request_part::RequestPart;
+use crate::handler::request_part::ShouldBeRequestPart;
+use crate::schema_generator::SchemaGenerator;
+use crate::stuff::api_error::ApiError;
+
+/// A single uuid path parameter
+///
+/// ```rust,ignore
+/// #[get("/users/{uuid}")]
+/// async fn get_user(UuidPath(uuid): UuidPath) -> ApiResult<Json<User>> {
+///     // ...
+/// }
+/// ```
+pub type UuidPath = ApiPath<uuid::Uuid>;
+
+/// Extractor for path parameters like axum's [`Path`]
+///
+/// Unlike `Path` it responds with a `400 Bad Request` [`ApiError`]
+/// if the parameters fail to deserialize (for example a malformed uuid).
+#[derive(Copy, Clone, Debug)]
+pub struct ApiPath<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ApiPath<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<T>::from_request_parts(parts, state).await {
+            Ok(Path(value)) => Ok(Self(value)),
+            Err(PathRejection::FailedToDeserializePathParams(error)) => {
+                Err(ApiError::client_error(error.body_text()).into_response())
+            }
+            Err(rejection) => Err(rejection.into_response()),
+        }
+    }
+}
+
+impl<T> ShouldBeRequestPart for ApiPath<T> {}
+impl<T: DeserializeOwned + JsonSchema> RequestPart for ApiPath<T> {
+    fn path_parameters(gen: &mut SchemaGenerator) -> Option<PathParameters> {
+        Path::<T>::path_parameters(gen)
+    }
+}
+
+impl<T> Deref for ApiPath<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for ApiPath<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
@@ -0,0 +1,189 @@
+//! The address of the client which sent a request
+//!
+//! When the server is deployed behind reverse proxies (load balancers, CDNs, ...)
+//! the connection's peer address is the one of the last proxy instead of the client.
+//! Proxies pass on the original address using one of the following headers:
+//! - `Forwarded` ([RFC 7239](https://www.rfc-editor.org/rfc/rfc7239))
+//! - `X-Forwarded-For`
+//! - `X-Real-IP`
+//!
+//! # Spoofing
+//!
+//! These headers are set by the client as well and can contain arbitrary values.
+//! They are therefore only evaluated if the request was received from a proxy
+//! registered with [`TrustedProxies::set_global`].
+//! Every proxy is expected to append the address it received the request from,
+//! so the [`ClientIp`] is the last address in the chain which isn't a trusted proxy.
+//!
+//! Never register a proxy which passes on these headers from its clients unmodified.
+//! Also make sure the server can't be reached directly, bypassing the proxies.
+
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+
+use axum::extract::ConnectInfo;
+use axum::extract::FromRequestParts;
+use axum::http::header;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use axum::http::HeaderName;
+
+use crate::handler::request_part::RequestPart;
+use crate::handler::request_part::ShouldBeRequestPart;
+use crate::stuff::api_error::ApiError;
+
+/// Header containing the addresses a request has been forwarded for
+pub static X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// Header containing the address of the client a request has been received from
+pub static X_REAL_IP: HeaderName = HeaderName::from_static("x-real-ip");
+
+/// The addresses of the reverse proxies whose forwarding headers are trusted
+///
+/// By default, no proxy is trusted and [`ClientIp`] is always the connection's peer address.
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies(pub Vec<IpAddr>);
+
+static TRUSTED_PROXIES: OnceLock<TrustedProxies> = OnceLock::new();
+
+impl TrustedProxies {
+    /// Sets the proxies [`ClientIp`] should trust
+    ///
+    /// This can only be set once and should happen before any routes are served.
+    /// Returns the rejected proxies if they have already been set.
+    pub fn set_global(self) -> Result<(), TrustedProxies> {
+        TRUSTED_PROXIES.set(self)
+    }
+
+    /// Gets the proxies [`ClientIp`] trusts
+    pub fn global() -> &'static TrustedProxies {
+        TRUSTED_PROXIES.get_or_init(TrustedProxies::default)
+    }
+
+    /// Checks whether an address belongs to a trusted proxy
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.0.iter().any(|proxy| proxy.to_canonical() == ip)
+    }
+}
+
+/// Extractor for the address of the client which sent the request
+///
+/// It reads the forwarding headers set by [`TrustedProxies`]
+/// and falls back to the connection's peer address.
+/// Read the [module's docs](self) before trusting any proxies.
+///
+/// The peer address is provided by [`ConnectInfo`],
+/// so the server has to be started with [`Router::into_make_service_with_connect_info`](axum::Router::into_make_service_with_connect_info).
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ClientIp(pub IpAddr);
+
+impl ClientIp {
+    /// Determines the client's address from the connection's peer address and the request's headers
+    pub fn resolve(peer: IpAddr, headers: &HeaderMap, proxies: &TrustedProxies) -> Self {
+        let peer = peer.to_canonical();
+        if !proxies.contains(peer) {
+            return Self(peer);
+        }
+
+        let mut chain = forwarded_for(headers);
+        if chain.is_empty() {
+            chain = x_forwarded_for(headers);
+        }
+        if chain.is_empty() {
+            chain = headers
+                .get(&X_REAL_IP)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| vec![parse_node(value)])
+                .unwrap_or_default();
+        }
+
+        // Walk the chain backwards starting at the trusted peer.
+        // The first address not belonging to a trusted proxy is the client.
+        let mut client = peer;
+        for node in chain.into_iter().rev() {
+            let Some(ip) = node else {
+                // A trusted proxy didn't know where it received the request from
+                break;
+            };
+            client = ip.to_canonical();
+            if !proxies.contains(client) {
+                break;
+            }
+        }
+        Self(client)
+    }
+}
+
+/// Collects the `for` parameters of the `Forwarded` headers
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    headers
+        .get_all(header::FORWARDED)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let (key, value) = pair.split_once('=')?;
+                key.trim()
+                    .eq_ignore_ascii_case("for")
+                    .then(|| parse_node(value))
+            })
+        })
+        .collect()
+}
+
+/// Collects the addresses of the `X-Forwarded-For` headers
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    headers
+        .get_all(&X_FORWARDED_FOR)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(parse_node)
+        .collect()
+}
+
+/// Parses a single address which might be quoted, bracketed or contain a port
+///
+/// Returns `None` for obfuscated identifiers and the value `unknown`.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    node.parse::<IpAddr>()
+        .or_else(|_| node.parse::<SocketAddr>().map(|addr| addr.ip()))
+        .or_else(|_| {
+            node.trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+        })
+        .ok()
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let ConnectInfo(peer) = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .copied()
+            .ok_or_else(|| {
+                ApiError::server_error("The server has been started without `ConnectInfo`")
+            })?;
+        Ok(Self::resolve(
+            peer.ip(),
+            &parts.headers,
+            TrustedProxies::global(),
+        ))
+    }
+}
+
+impl ShouldBeRequestPart for ClientIp {}
+impl RequestPart for ClientIp {}
+
+impl From<ClientIp> for IpAddr {
+    fn from(ClientIp(ip): ClientIp) -> Self {
+        ip
+    }
+}
@@ -0,0 +1,53 @@
+//! Restricting the content types a handler accepts
+//!
+//! Handlers declare their accepted content types using `#[handler(..., accept("application/json"))]`.
+//! Requests with a body of any other content type are rejected with `415 Unsupported Media Type`
+//! before the body is parsed.
+
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::header;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use mime::Mime;
+
+use crate::stuff::api_error::ApiError;
+
+/// Middleware rejecting requests whose content type is not in the allow-list
+///
+/// This is applied to every handler declaring `accept(...)` by [`GalvynRouter::handler`](crate::GalvynRouter::handler).
+pub(crate) async fn middleware(
+    State(accept): State<&'static [&'static str]>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<Mime>().ok());
+
+    let allowed = match content_type {
+        Some(content_type) => accept
+            .iter()
+            .any(|accepted| content_type.essence_str().eq_ignore_ascii_case(accepted)),
+        // Requests without a body don't need a content type
+        None => {
+            !headers.contains_key(header::TRANSFER_ENCODING)
+                && headers
+                    .get(header::CONTENT_LENGTH)
+                    .is_none_or(|length| length == "0")
+        }
+    };
+
+    if allowed {
+        next.run(request).await
+    } else {
+        ApiError::unsupported_media_type(format!(
+            "Expected one of the content types: {}",
+            accept.join(", ")
+        ))
+        .into_response()
+    }
+}
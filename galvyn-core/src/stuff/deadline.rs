@@ -0,0 +1,151 @@
+//! Deadlines propagated by the caller of a request
+//!
+//! In distributed systems a caller often only waits a limited amount of time for a response.
+//! It can communicate this limit using one of the following headers:
+//! - `X-Request-Deadline`: the absolute deadline as milliseconds since the unix epoch
+//! - `grpc-timeout`: a relative timeout in the format used by gRPC (for example `100m` for 100 milliseconds)
+//!
+//! The [`middleware`] parses these headers, aborts requests which exceed their deadline with
+//! `504 Gateway Timeout` and makes the deadline available to handlers through the [`Deadline`] extractor:
+//!
+//! ```rust,ignore
+//! let router = GalvynRouter::new()
+//!     .handler(my_handler)
+//!     .layer(axum::middleware::from_fn(deadline::middleware));
+//! ```
+
+use std::convert::Infallible;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use axum::extract::FromRequestParts;
+use axum::extract::Request;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use axum::http::HeaderName;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Response;
+
+use crate::handler::request_part::RequestPart;
+use crate::handler::request_part::ShouldBeRequestPart;
+
+/// Header containing the absolute deadline as milliseconds since the unix epoch
+pub static REQUEST_DEADLINE: HeaderName = HeaderName::from_static("x-request-deadline");
+
+/// Header containing a relative timeout in the format used by gRPC
+pub static GRPC_TIMEOUT: HeaderName = HeaderName::from_static("grpc-timeout");
+
+/// Extractor for the deadline set by the request's caller
+///
+/// It requires the [`middleware`] to be applied to the router.
+/// Without it, every request will appear to have no deadline.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Deadline(Option<Instant>);
+
+impl Deadline {
+    /// The point in time after which the caller is no longer interested in a response
+    ///
+    /// Returns `None` if the caller didn't set a deadline.
+    pub fn instant(&self) -> Option<Instant> {
+        self.0
+    }
+
+    /// The time remaining until the deadline is reached
+    ///
+    /// Returns `None` if the caller didn't set a deadline
+    /// and [`Duration::ZERO`] if it has already passed.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.0
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Checks whether the deadline has already passed
+    pub fn is_expired(&self) -> bool {
+        self.remaining() == Some(Duration::ZERO)
+    }
+
+    /// Parses the deadline from a request's headers
+    ///
+    /// If both supported headers are present, the earlier deadline is used.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let now = Instant::now();
+
+        let absolute = headers
+            .get(&REQUEST_DEADLINE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(|millis| {
+                let deadline = UNIX_EPOCH + Duration::from_millis(millis);
+                now + deadline
+                    .duration_since(SystemTime::now())
+                    .unwrap_or(Duration::ZERO)
+            });
+
+        let relative = headers
+            .get(&GRPC_TIMEOUT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_grpc_timeout)
+            .map(|timeout| now + timeout);
+
+        Self(match (absolute, relative) {
+            (Some(absolute), Some(relative)) => Some(absolute.min(relative)),
+            (deadline, None) | (None, deadline) => deadline,
+        })
+    }
+}
+
+/// Parses a `grpc-timeout` value: up to 8 digits followed by a unit (`H`, `M`, `S`, `m`, `u` or `n`)
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    let amount = amount.parse::<u64>().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(amount * 60 * 60),
+        "M" => Duration::from_secs(amount * 60),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    })
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Deadline {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<Deadline>()
+            .copied()
+            .unwrap_or_default())
+    }
+}
+
+impl ShouldBeRequestPart for Deadline {}
+impl RequestPart for Deadline {}
+
+/// Middleware parsing the request's deadline
+///
+/// It stores the [`Deadline`] for handlers to extract and responds with `504 Gateway Timeout`
+/// if the request's handling exceeds the deadline.
+pub async fn middleware(mut request: Request, next: Next) -> Response {
+    let deadline = Deadline::from_headers(request.headers());
+    request.extensions_mut().insert(deadline);
+
+    match deadline.instant() {
+        None => next.run(request).await,
+        Some(_) if deadline.is_expired() => StatusCode::GATEWAY_TIMEOUT.into_response(),
+        Some(instant) => match tokio::time::timeout_at(instant.into(), next.run(request)).await {
+            Ok(response) => response,
+            Err(_) => StatusCode::GATEWAY_TIMEOUT.into_response(),
+        },
+    }
+}
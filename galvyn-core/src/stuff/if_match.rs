@@ -0,0 +1,150 @@
+//! Optimistic concurrency control using the `If-Match` header
+//!
+//! A client remembers the [`ETag`] it received with a resource and sends it back in the `If-Match` header
+//! when updating the resource. The update is only performed if the resource has not changed in the meantime:
+//!
+//! ```rust,ignore
+//! #[put("/articles/{id}")]
+//! async fn update_article(
+//!     if_match: IfMatch,
+//!     Path(id): Path<Uuid>,
+//!     Json(request): Json<UpdateArticleRequest>,
+//! ) -> ApiResult<()> {
+//!     let article = /* ... */;
+//!     if_match.check(&article.version.to_string())?;
+//!     // ...
+//! }
+//! ```
+
+use std::convert::Infallible;
+
+use axum::extract::FromRequestParts;
+use axum::http::header;
+use axum::http::request::Parts;
+use axum::http::HeaderValue;
+use axum::response::IntoResponseParts;
+use axum::response::ResponseParts;
+
+use crate::handler::request_part::RequestPart;
+use crate::handler::request_part::ShouldBeRequestPart;
+use crate::handler::response_part::ResponseHeader;
+use crate::handler::response_part::ResponsePart;
+use crate::handler::response_part::ShouldBeResponsePart;
+use crate::schema_generator::SchemaGenerator;
+use crate::stuff::api_error::ApiError;
+
+/// Extractor for the `If-Match` header
+///
+/// If the header is missing, every entity tag matches.
+#[derive(Clone, Debug, Default)]
+pub struct IfMatch(Option<Condition>);
+
+#[derive(Clone, Debug)]
+enum Condition {
+    /// `If-Match: *`
+    Any,
+
+    /// The listed strong entity tags without their quotes
+    Tags(Vec<String>),
+}
+
+impl IfMatch {
+    /// Checks whether the request contained an `If-Match` header
+    pub fn is_present(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Checks whether the current entity tag of a resource matches the header
+    ///
+    /// The tag may be passed with or without its surrounding quotes.
+    /// As required for `If-Match`, the comparison is strong i.e. weak tags (`W/"..."`) never match.
+    pub fn matches(&self, etag: &str) -> bool {
+        let etag = etag
+            .strip_prefix('"')
+            .and_then(|etag| etag.strip_suffix('"'))
+            .unwrap_or(etag);
+        match &self.0 {
+            None | Some(Condition::Any) => true,
+            Some(Condition::Tags(tags)) => tags.iter().any(|tag| tag == etag),
+        }
+    }
+
+    /// Returns a `412 Precondition Failed` error if the current entity tag doesn't match the header
+    #[track_caller]
+    pub fn check(&self, etag: &str) -> Result<(), ApiError> {
+        if self.matches(etag) {
+            Ok(())
+        } else {
+            Err(ApiError::precondition_failed(
+                "The resource has been modified",
+            ))
+        }
+    }
+
+    /// Parses the `If-Match` header's value
+    fn parse(value: &str) -> Condition {
+        if value.trim() == "*" {
+            return Condition::Any;
+        }
+        Condition::Tags(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter_map(|tag| tag.strip_prefix('"')?.strip_suffix('"'))
+                .map(str::to_string)
+                .collect(),
+        )
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let mut values = parts.headers.get_all(header::IF_MATCH).iter().peekable();
+        if values.peek().is_none() {
+            return Ok(Self(None));
+        }
+
+        let mut tags = Vec::new();
+        for value in values {
+            // A header which isn't valid ascii can't contain a valid tag and therefore matches nothing
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            match Self::parse(value) {
+                Condition::Any => return Ok(Self(Some(Condition::Any))),
+                Condition::Tags(parsed) => tags.extend(parsed),
+            }
+        }
+        Ok(Self(Some(Condition::Tags(tags))))
+    }
+}
+
+impl ShouldBeRequestPart for IfMatch {}
+impl RequestPart for IfMatch {}
+
+/// Response part setting the `ETag` header to a resource's current entity tag
+///
+/// The tag is passed without its surrounding quotes, like to [`IfMatch::check`].
+#[derive(Clone, Debug)]
+pub struct ETag(pub String);
+
+impl IntoResponseParts for ETag {
+    type Error = ApiError;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        let value =
+            HeaderValue::try_from(format!("\"{}\"", self.0)).map_err(ApiError::server_error)?;
+        res.headers_mut().insert(header::ETAG, value);
+        Ok(res)
+    }
+}
+
+impl ShouldBeResponsePart for ETag {}
+impl ResponsePart for ETag {
+    fn header(_gen: &mut SchemaGenerator) -> Vec<ResponseHeader> {
+        vec![ResponseHeader::new(header::ETAG)
+            .description("The resource's current entity tag to be sent in `If-Match`")]
+    }
+}
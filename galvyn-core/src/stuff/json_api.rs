@@ -0,0 +1,226 @@
+//! Request and response bodies following the [JSON:API](https://jsonapi.org) specification
+//!
+//! [`JsonApi<T>`] is used like axum's [`Json<T>`](axum::Json) where `T` is the document's primary data.
+//! This is usually a [`Resource`] or a `Vec<Resource>`:
+//!
+//! ```rust,ignore
+//! #[get("/articles/{id}")]
+//! async fn get_article(Path(id): Path<String>) -> ApiResult<JsonApi<Resource<Article>>> {
+//!     let article = /* ... */;
+//!     Ok(JsonApi::new(Resource::new("articles", id, article)))
+//! }
+//! ```
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use axum::body::Bytes;
+use axum::extract::FromRequest;
+use axum::extract::Request;
+use axum::http::header;
+use axum::http::HeaderValue;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use mime::Mime;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::handler::request_body::RequestBody;
+use crate::handler::request_body::ShouldBeRequestBody;
+use crate::handler::response_body::ResponseBody;
+use crate::handler::response_body::ShouldBeResponseBody;
+use crate::schema_generator::SchemaGenerator;
+
+/// The media type of JSON:API documents
+pub const APPLICATION_VND_API_JSON: &str = "application/vnd.api+json";
+
+/// A JSON:API document containing the primary data `T`
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct JsonApi<T> {
+    /// The document's primary data
+    pub data: T,
+
+    /// Resources related to the primary data
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub included: Vec<Resource<serde_json::Value>>,
+}
+
+impl<T> JsonApi<T> {
+    /// Constructs a document without any included resources
+    pub fn new(data: T) -> Self {
+        Self {
+            data,
+            included: Vec::new(),
+        }
+    }
+
+    /// Adds a related resource to the document's `included` resources
+    ///
+    /// # Errors
+    /// If the resource's attributes fail to serialize
+    pub fn include<A: Serialize>(mut self, resource: Resource<A>) -> serde_json::Result<Self> {
+        self.included.push(Resource {
+            r#type: resource.r#type,
+            id: resource.id,
+            attributes: serde_json::to_value(resource.attributes)?,
+            relationships: resource.relationships,
+        });
+        Ok(self)
+    }
+}
+
+/// A JSON:API resource object
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Resource<A> {
+    /// The resource's type
+    #[serde(rename = "type")]
+    pub r#type: Cow<'static, str>,
+
+    /// The resource's id
+    pub id: String,
+
+    /// The resource's attributes
+    pub attributes: A,
+
+    /// The resource's relationships to other resources
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub relationships: BTreeMap<String, Relationship>,
+}
+
+impl<A> Resource<A> {
+    /// Constructs a resource without any relationships
+    pub fn new(r#type: impl Into<Cow<'static, str>>, id: impl ToString, attributes: A) -> Self {
+        Self {
+            r#type: r#type.into(),
+            id: id.to_string(),
+            attributes,
+            relationships: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a relationship to the resource
+    pub fn relationship(mut self, name: impl Into<String>, data: RelationshipData) -> Self {
+        self.relationships
+            .insert(name.into(), Relationship { data });
+        self
+    }
+
+    /// Gets the identifier referring to this resource
+    pub fn identifier(&self) -> ResourceIdentifier {
+        ResourceIdentifier {
+            r#type: self.r#type.clone(),
+            id: self.id.clone(),
+        }
+    }
+}
+
+/// A JSON:API relationship object
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Relationship {
+    /// The related resources
+    pub data: RelationshipData,
+}
+
+/// Linkage to the resources in a relationship
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[serde(untagged)]
+pub enum RelationshipData {
+    /// A to-one relationship which might be empty
+    One(Option<ResourceIdentifier>),
+
+    /// A to-many relationship
+    Many(Vec<ResourceIdentifier>),
+}
+
+/// A JSON:API resource identifier object
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, Eq, PartialEq)]
+pub struct ResourceIdentifier {
+    /// The resource's type
+    #[serde(rename = "type")]
+    pub r#type: Cow<'static, str>,
+
+    /// The resource's id
+    pub id: String,
+}
+
+impl<T, S> FromRequest<S> for JsonApi<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let has_content_type = request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .and_then(|content_type| content_type.parse::<Mime>().ok())
+            .is_some_and(|mime| mime.essence_str() == APPLICATION_VND_API_JSON);
+        if !has_content_type {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Expected request with `Content-Type: {APPLICATION_VND_API_JSON}`"),
+            )
+                .into_response());
+        }
+
+        let bytes = Bytes::from_request(request, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        serde_json::from_slice(&bytes).map_err(|error| {
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Failed to deserialize the JSON:API document: {error}"),
+            )
+                .into_response()
+        })
+    }
+}
+
+impl<T: Serialize> IntoResponse for JsonApi<T> {
+    fn into_response(self) -> Response {
+        match serde_json::to_vec(&self) {
+            Ok(body) => (
+                [(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static(APPLICATION_VND_API_JSON),
+                )],
+                body,
+            )
+                .into_response(),
+            Err(error) => {
+                warn!(error.display = %error, "Failed to serialize JSON:API document");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        }
+    }
+}
+
+impl<T> ShouldBeRequestBody for JsonApi<T> {}
+impl<T: DeserializeOwned + JsonSchema> RequestBody for JsonApi<T> {
+    fn body(gen: &mut SchemaGenerator) -> (Mime, Option<Schema>) {
+        (json_api_mime(), Some(gen.generate::<JsonApi<T>>()))
+    }
+}
+
+impl<T> ShouldBeResponseBody for JsonApi<T> {}
+impl<T: Serialize + JsonSchema> ResponseBody for JsonApi<T> {
+    fn body(gen: &mut SchemaGenerator) -> Vec<(StatusCode, Option<(Mime, Option<Schema>)>)> {
+        vec![(
+            StatusCode::OK,
+            Some((json_api_mime(), Some(gen.generate::<JsonApi<T>>()))),
+        )]
+    }
+}
+
+fn json_api_mime() -> Mime {
+    APPLICATION_VND_API_JSON
+        .parse()
+        .unwrap_or_else(|_| unreachable!("The media type is valid"))
+}
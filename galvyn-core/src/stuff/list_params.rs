@@ -0,0 +1,186 @@
+//! Typed sorting, filtering and pagination for list endpoints
+//!
+//! ```rust,ignore
+//! #[derive(Deserialize, JsonSchema)]
+//! #[serde(rename_all = "camelCase")]
+//! enum UserSort {
+//!     Name,
+//!     CreatedAt,
+//! }
+//!
+//! #[derive(Deserialize, JsonSchema)]
+//! struct UserFilter {
+//!     admin: Option<bool>,
+//! }
+//!
+//! // GET /users?sort=-createdAt,name&admin=true&limit=20&offset=40
+//! #[get("/users")]
+//! async fn get_users(params: ListParams<UserSort, UserFilter>) -> ApiResult<Json<Page<User>>> {
+//!     // ...
+//! }
+//! ```
+
+use axum::extract::FromRequestParts;
+use axum::extract::Query;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use schemars::schema::InstanceType;
+use schemars::schema::Metadata;
+use schemars::schema::Schema;
+use schemars::schema::SchemaObject;
+use schemars::JsonSchema;
+use serde::de::value::Error as ValueError;
+use serde::de::value::StrDeserializer;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::handler::request_part::QueryParameter;
+use crate::handler::request_part::RequestPart;
+use crate::handler::request_part::ShouldBeRequestPart;
+use crate::schema_generator::SchemaGenerator;
+
+/// Extractor for the query parameters of a list endpoint
+///
+/// - `sort`: a comma separated list of `S`'s variants.
+///   A variant prefixed with `-` sorts in descending order.
+/// - `limit` and `offset`: select a page of the list
+/// - any other parameter is deserialized into the filter `F`
+///
+/// Since `F` is deserialized from the entire query, it must not use `#[serde(deny_unknown_fields)]`.
+#[derive(Clone, Debug)]
+pub struct ListParams<S, F> {
+    /// The fields to sort by in order of precedence
+    pub sort: Vec<Sort<S>>,
+
+    /// The filter to apply
+    pub filter: F,
+
+    /// The maximum number of items to return
+    pub limit: Option<u64>,
+
+    /// The number of items to skip
+    pub offset: u64,
+}
+
+/// A field to sort by
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Sort<S> {
+    /// The field to sort by
+    pub field: S,
+
+    /// Whether to sort in descending order
+    pub descending: bool,
+}
+
+#[derive(Deserialize)]
+struct RawListParams {
+    sort: Option<String>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+}
+
+impl<S, F, State> FromRequestParts<State> for ListParams<S, F>
+where
+    S: DeserializeOwned,
+    F: DeserializeOwned,
+    State: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &State) -> Result<Self, Self::Rejection> {
+        let Query(RawListParams {
+            sort,
+            limit,
+            offset,
+        }) = Query::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        let Query(filter) = Query::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let sort = sort
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .filter(|field| !field.is_empty())
+            .map(|field| {
+                let (name, descending) = match field.strip_prefix('-') {
+                    Some(name) => (name, true),
+                    None => (field, false),
+                };
+                S::deserialize(StrDeserializer::<ValueError>::new(name))
+                    .map(|field| Sort { field, descending })
+                    .map_err(|error| {
+                        (
+                            StatusCode::BAD_REQUEST,
+                            format!("Invalid sort field `{name}`: {error}"),
+                        )
+                            .into_response()
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            sort,
+            filter,
+            limit,
+            offset: offset.unwrap_or(0),
+        })
+    }
+}
+
+impl<S, F> ShouldBeRequestPart for ListParams<S, F> {}
+impl<S, F> RequestPart for ListParams<S, F>
+where
+    S: DeserializeOwned + JsonSchema,
+    F: DeserializeOwned + JsonSchema,
+{
+    fn query_parameters(gen: &mut SchemaGenerator) -> Vec<QueryParameter> {
+        let mut description = "Comma separated list of fields to sort by, \
+            prefix a field with `-` to sort descending."
+            .to_string();
+        if let Schema::Object(SchemaObject {
+            enum_values: Some(fields),
+            ..
+        }) = gen.generate_refless::<S>()
+        {
+            let fields = fields
+                .iter()
+                .filter_map(|field| field.as_str())
+                .map(|field| format!("`{field}`"))
+                .collect::<Vec<_>>();
+            description.push_str(&format!("\n\nFields: {}", fields.join(", ")));
+        }
+
+        let mut parameters = vec![
+            QueryParameter {
+                name: "sort".to_string(),
+                required: false,
+                schema: SchemaObject {
+                    metadata: Some(Box::new(Metadata {
+                        description: Some(description),
+                        ..Default::default()
+                    })),
+                    instance_type: Some(InstanceType::String.into()),
+                    ..Default::default()
+                }
+                .into(),
+            },
+            QueryParameter {
+                name: "limit".to_string(),
+                required: false,
+                schema: gen.generate::<u64>(),
+            },
+            QueryParameter {
+                name: "offset".to_string(),
+                required: false,
+                schema: gen.generate::<u64>(),
+            },
+        ];
+        parameters.extend(Query::<F>::query_parameters(gen));
+        parameters
+    }
+}
@@ -1,5 +1,20 @@
 //! TODO: better naming and grouping of content
 //!       => better everything
 
+pub mod api_docs;
 pub mod api_error;
 pub mod api_json;
+pub mod api_path;
+pub mod client_ip;
+pub mod content_type;
+pub mod deadline;
+pub mod if_match;
+#[cfg(feature = "json-api")]
+pub mod json_api;
+pub mod list_params;
+pub mod pagination;
+pub mod request_observer;
+pub mod rorm_ext;
+pub mod streaming_json;
+pub mod trailing_slash;
+pub mod try_from_request_body;
@@ -0,0 +1,209 @@
+//! Paginated responses
+//!
+//! A paginated handler can document its pagination in the body, in headers or both:
+//! - body: return `Json<Page<T>>`
+//! - headers: return `(PaginationHeaders, Json<Vec<T>>)`
+//! - both: return `(PaginationHeaders, Json<Page<T>>)`
+//!
+//! ```rust,ignore
+//! #[get("/users")]
+//! async fn get_users(
+//!     OriginalUri(uri): OriginalUri,
+//!     Query(params): Query<PageParams>,
+//! ) -> ApiResult<(PaginationHeaders, Json<Page<User>>)> {
+//!     let page = /* ... */;
+//!     Ok((page.headers(&uri), Json(page)))
+//! }
+//! ```
+
+use std::fmt::Write;
+
+use axum::http::header;
+use axum::http::HeaderName;
+use axum::http::HeaderValue;
+use axum::http::Uri;
+use axum::response::IntoResponseParts;
+use axum::response::ResponseParts;
+use schemars::JsonSchema;
+use serde::de::Error as _;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+
+use crate::handler::response_part::ResponseHeader;
+use crate::handler::response_part::ResponsePart;
+use crate::handler::response_part::ShouldBeResponsePart;
+use crate::schema_generator::SchemaGenerator;
+
+/// The header containing the total number of items
+pub static X_TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+
+/// The query parameters selecting a page
+#[derive(Deserialize, JsonSchema, Copy, Clone, Debug)]
+pub struct PageParams {
+    /// The maximum number of items to return
+    ///
+    /// It has to be at least `1`.
+    #[serde(deserialize_with = "deserialize_limit")]
+    #[schemars(range(min = 1))]
+    pub limit: u64,
+
+    /// The number of items to skip
+    pub offset: u64,
+}
+
+/// Rejects a `limit` of `0`, whose pages would all be empty and link to themselves
+fn deserialize_limit<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let limit = u64::deserialize(deserializer)?;
+    if limit == 0 {
+        return Err(D::Error::custom("limit has to be at least 1"));
+    }
+    Ok(limit)
+}
+
+/// A page of items
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+pub struct Page<T> {
+    /// The page's items
+    pub items: Vec<T>,
+
+    /// The limit this page was requested with
+    pub limit: u64,
+
+    /// The offset this page was requested with
+    pub offset: u64,
+
+    /// The total number of items
+    pub total: u64,
+}
+
+impl<T> Page<T> {
+    /// Constructs the headers describing this page
+    ///
+    /// `uri` is the uri this page was requested with (use [`OriginalUri`](axum::extract::OriginalUri)).
+    /// It is used as base for the links to the other pages.
+    pub fn headers(&self, uri: &Uri) -> PaginationHeaders {
+        PaginationHeaders::new(
+            uri,
+            PageParams {
+                limit: self.limit,
+                offset: self.offset,
+            },
+            self.total,
+        )
+    }
+}
+
+/// Response headers describing a page
+///
+/// - `Link` (RFC 8288) containing `rel="first"`, `rel="prev"`, `rel="next"` and `rel="last"` links
+///
+/// `prev` and `next` are left out on the first and last page respectively.
+/// - `X-Total-Count` containing the total number of items
+#[derive(Clone, Debug)]
+pub struct PaginationHeaders {
+    link: Option<HeaderValue>,
+    total: u64,
+}
+
+impl PaginationHeaders {
+    /// Constructs the headers for a page
+    ///
+    /// `uri` is the uri the page was requested with (use [`OriginalUri`](axum::extract::OriginalUri)).
+    /// Its `limit` and `offset` query parameters are replaced to construct the links.
+    /// No links are constructed for a `limit` of `0`, since they would point to the same page.
+    pub fn new(uri: &Uri, params: PageParams, total: u64) -> Self {
+        let PageParams { limit, offset } = params;
+
+        let mut links = Vec::new();
+        if limit > 0 {
+            links.push((0, "first"));
+            if offset > 0 {
+                links.push((offset.saturating_sub(limit), "prev"));
+            }
+            if offset.saturating_add(limit) < total {
+                links.push((offset + limit, "next"));
+            }
+            links.push((total.saturating_sub(1) / limit * limit, "last"));
+        }
+
+        let link = links
+            .into_iter()
+            .map(|(offset, rel)| format!("<{}>; rel=\"{rel}\"", page_uri(uri, limit, offset)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        Self {
+            link: HeaderValue::try_from(link)
+                .ok()
+                .filter(|link| !link.is_empty()),
+            total,
+        }
+    }
+}
+
+/// Replaces the `limit` and `offset` query parameters in an uri
+fn page_uri(uri: &Uri, limit: u64, offset: u64) -> String {
+    let mut page_uri = uri.path().to_string();
+    page_uri.push('?');
+    for pair in uri.query().unwrap_or_default().split('&') {
+        let key = pair.split_once('=').map_or(pair, |(key, _)| key);
+        if !pair.is_empty() && key != "limit" && key != "offset" {
+            page_uri.push_str(pair);
+            page_uri.push('&');
+        }
+    }
+    let _ = write!(page_uri, "limit={limit}&offset={offset}");
+    page_uri
+}
+
+impl IntoResponseParts for PaginationHeaders {
+    type Error = std::convert::Infallible;
+
+    fn into_response_parts(self, mut res: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        if let Some(link) = self.link {
+            res.headers_mut().insert(header::LINK, link);
+        }
+        res.headers_mut()
+            .insert(X_TOTAL_COUNT.clone(), HeaderValue::from(self.total));
+        Ok(res)
+    }
+}
+
+impl ShouldBeResponsePart for PaginationHeaders {}
+impl ResponsePart for PaginationHeaders {
+    fn header(gen: &mut SchemaGenerator) -> Vec<ResponseHeader> {
+        vec![
+            ResponseHeader::new(header::LINK)
+                .description("Links to the `first`, `prev`, `next` and `last` pages"),
+            ResponseHeader::new(X_TOTAL_COUNT.clone())
+                .description("The total number of items")
+                .schema(gen.generate::<u64>()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_links_include_the_first_and_last_page() {
+        let uri = Uri::from_static("/users?admin=true&limit=10&offset=10");
+        let headers = PaginationHeaders::new(
+            &uri,
+            PageParams {
+                limit: 10,
+                offset: 10,
+            },
+            25,
+        );
+        assert_eq!(
+            headers.link.unwrap(),
+            "</users?admin=true&limit=10&offset=0>; rel=\"first\", \
+             </users?admin=true&limit=10&offset=0>; rel=\"prev\", \
+             </users?admin=true&limit=10&offset=20>; rel=\"next\", \
+             </users?admin=true&limit=10&offset=20>; rel=\"last\""
+        );
+    }
+}
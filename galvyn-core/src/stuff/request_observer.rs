@@ -0,0 +1,90 @@
+//! Observing every request handled by a [`GalvynHandler`](crate::GalvynHandler)
+//!
+//! Modules like metrics or audit logs need to see every request without each of them wrapping the router
+//! in its own middleware. Instead, they implement [`RequestObserver`] and register it once:
+//!
+//! ```rust,ignore
+//! struct Metrics;
+//! impl RequestObserver for Metrics {
+//!     fn on_response(&self, meta: &HandlerMeta, response: &Response, duration: Duration) {
+//!         info!(handler = meta.ident, status = %response.status(), ?duration);
+//!     }
+//! }
+//!
+//! register_observer(Metrics);
+//! ```
+//!
+//! Every handler added through [`GalvynRouter::handler`](crate::GalvynRouter::handler) invokes
+//! all registered observers.
+
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
+
+use axum::extract::Request;
+use axum::extract::State;
+use axum::middleware::Next;
+use axum::response::Response;
+
+use crate::handler::HandlerMeta;
+
+/// Observes the requests handled by any [`GalvynHandler`](crate::GalvynHandler)
+///
+/// Observers are called synchronously on the request's task and should therefore return quickly.
+pub trait RequestObserver: Send + Sync + 'static {
+    /// Called before the handler processes the request
+    fn on_request(&self, _meta: &HandlerMeta, _request: &Request) {}
+
+    /// Called after the handler produced its response
+    ///
+    /// `duration` is the time it took the handler (and all middlewares applied to it) to respond.
+    fn on_response(&self, _meta: &HandlerMeta, _response: &Response, _duration: Duration) {}
+}
+
+static OBSERVERS: RwLock<Vec<Arc<dyn RequestObserver>>> = RwLock::new(Vec::new());
+
+/// Registers an observer which will be invoked for every request
+///
+/// Observers should be registered before the server starts (for example in [`Module::init`](crate::Module::init)).
+pub fn register_observer(observer: impl RequestObserver) {
+    OBSERVERS
+        .write()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .push(Arc::new(observer));
+}
+
+/// Returns a snapshot of the currently registered observers
+fn observers() -> Vec<Arc<dyn RequestObserver>> {
+    OBSERVERS
+        .read()
+        .unwrap_or_else(|poison| poison.into_inner())
+        .clone()
+}
+
+/// Middleware invoking all registered observers
+///
+/// This is applied to every handler by [`GalvynRouter::handler`](crate::GalvynRouter::handler).
+pub(crate) async fn middleware(
+    State(meta): State<Arc<HandlerMeta>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let observers = observers();
+    if observers.is_empty() {
+        return next.run(request).await;
+    }
+
+    for observer in &observers {
+        observer.on_request(&meta, &request);
+    }
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let duration = start.elapsed();
+
+    for observer in &observers {
+        observer.on_response(&meta, &response, duration);
+    }
+    response
+}
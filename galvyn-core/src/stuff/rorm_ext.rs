@@ -0,0 +1,131 @@
+//! Helpers for using rorm inside handlers
+
+use std::error::Error;
+use std::future::Future;
+use std::future::IntoFuture;
+use std::pin::Pin;
+use std::time::Duration;
+
+use rorm::db::Transaction;
+use rorm::Database;
+use tracing::debug;
+
+use crate::stuff::api_error::ApiError;
+use crate::stuff::api_error::ApiResult;
+use crate::stuff::api_error::DynError;
+
+/// Extension methods for the futures returned by rorm's `.optional()`
+///
+/// They replace the common `.optional().await?.ok_or(...)?` pattern:
+///
+/// ```rust,ignore
+/// let user = query!(db, User)
+///     .condition(User.id.equals(id))
+///     .optional()
+///     .or_not_found("User not found")
+///     .await?;
+/// ```
+pub trait OptionalExt<T>: IntoFuture<Output = Result<Option<T>, rorm::Error>> + Sized {
+    /// Converts a `None` into a `404 Not Found` [`ApiError`]
+    #[track_caller]
+    fn or_not_found(self, message: &'static str) -> impl Future<Output = ApiResult<T>> + Send;
+
+    /// Converts a `None` into the given [`ApiError`]
+    fn or_api_error(self, error: ApiError) -> impl Future<Output = ApiResult<T>> + Send;
+}
+
+impl<F, T> OptionalExt<T> for F
+where
+    F: IntoFuture<Output = Result<Option<T>, rorm::Error>> + Send,
+    F::IntoFuture: Send,
+    T: Send,
+{
+    #[track_caller]
+    fn or_not_found(self, message: &'static str) -> impl Future<Output = ApiResult<T>> + Send {
+        self.or_api_error(ApiError::not_found(message))
+    }
+
+    fn or_api_error(self, error: ApiError) -> impl Future<Output = ApiResult<T>> + Send {
+        async move {
+            match self.await? {
+                Some(value) => Ok(value),
+                None => Err(error.into()),
+            }
+        }
+    }
+}
+
+/// The future returned by the closure passed to [`retry_transaction`]
+pub type TransactionFuture<'tx, T> = Pin<Box<dyn Future<Output = ApiResult<T>> + Send + 'tx>>;
+
+/// How often [`retry_transaction`] runs its closure at most
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Runs a closure in a transaction which is retried on serialization failures and deadlocks
+///
+/// The transaction is committed after the closure returned successfully.
+/// If the closure or the commit fail due to a serialization failure or a deadlock
+/// (which is expected under concurrency with serializable isolation),
+/// the transaction is rolled back and the closure is run again in a new one after a short backoff.
+/// Any other error is returned immediately.
+///
+/// ```rust,ignore
+/// let account = retry_transaction(&db, |tx| {
+///     let name = name.clone();
+///     Box::pin(async move {
+///         let existing = query!(&mut *tx, Account)
+///             .condition(Account.name.equals(&name))
+///             .optional()
+///             .await?;
+///         // ...
+///     })
+/// })
+/// .await?;
+/// ```
+pub async fn retry_transaction<T, F>(db: &Database, mut f: F) -> ApiResult<T>
+where
+    F: for<'tx> FnMut(&'tx mut Transaction) -> TransactionFuture<'tx, T>,
+{
+    let mut attempt = 1;
+    loop {
+        let result = async {
+            let mut tx = db.start_transaction().await?;
+            let value = f(&mut tx).await?;
+            tx.commit().await?;
+            Ok(value)
+        }
+        .await;
+
+        match result {
+            Err(error) if attempt < MAX_ATTEMPTS && is_retryable(&error) => {
+                debug!(error.display = %**error, attempt, "Retrying transaction");
+                tokio::time::sleep(Duration::from_millis(10 << attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Checks whether an error (or any of its sources) is a serialization failure or a deadlock
+///
+/// rorm doesn't expose the database's error codes, so this has to match on the error messages.
+fn is_retryable(error: &DynError) -> bool {
+    let mut source: Option<&(dyn Error + 'static)> = Some(&***error);
+    while let Some(error) = source {
+        let message = error.to_string();
+        if [
+            "40001",
+            "40P01",
+            "could not serialize access",
+            "deadlock detected",
+        ]
+        .iter()
+        .any(|pattern| message.contains(pattern))
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
@@ -0,0 +1,102 @@
+//! A json response which is serialized while being sent
+
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+
+use axum::body::Body;
+use axum::http::header;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::Response;
+use bytes::Bytes;
+use futures_lite::stream;
+use mime::Mime;
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::handler::response_body::ResponseBody;
+use crate::handler::response_body::ShouldBeResponseBody;
+use crate::schema_generator::SchemaGenerator;
+
+/// Size of the chunks the serialized json is sent in
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Number of chunks which may be buffered before the serializer has to wait for the client
+const BUFFERED_CHUNKS: usize = 4;
+
+/// A json response which is serialized directly into the response body
+///
+/// Unlike [`Json`](axum::Json) it doesn't serialize the entire value into memory before sending it.
+/// Instead, the value is serialized on a blocking thread and sent in chunks as they are produced.
+/// Use this for large arrays or objects where a fully buffered response would spike the memory usage.
+///
+/// Since the size of the body is not known upfront,
+/// the response can't have a `Content-Length` and uses chunked transfer encoding instead.
+///
+/// If the serialization fails halfway, the status code has already been sent.
+/// The error will be logged and the body aborted, so the client receives an incomplete response.
+#[derive(Copy, Clone, Debug)]
+pub struct StreamingJson<T>(pub T);
+
+impl<T: Serialize + Send + 'static> IntoResponse for StreamingJson<T> {
+    fn into_response(self) -> Response {
+        let (sender, receiver) = mpsc::channel(BUFFERED_CHUNKS);
+
+        tokio::task::spawn_blocking(move || {
+            let mut writer = BufWriter::with_capacity(CHUNK_SIZE, ChannelWriter(sender.clone()));
+            let result = serde_json::to_writer(&mut writer, &self.0)
+                .map_err(io::Error::from)
+                .and_then(|()| writer.flush());
+            if let Err(error) = result {
+                if error.kind() != io::ErrorKind::BrokenPipe {
+                    warn!(error.display = %error, "Failed to serialize streaming json");
+                }
+                // The receiver might already be gone, in which case there is nobody to abort
+                let _ = sender.blocking_send(Err(error));
+            }
+        });
+
+        let body = Body::from_stream(stream::unfold(receiver, |mut receiver| async move {
+            let chunk = receiver.recv().await?;
+            Some((chunk, receiver))
+        }));
+
+        (
+            [(header::CONTENT_TYPE, mime::APPLICATION_JSON.as_ref())],
+            body,
+        )
+            .into_response()
+    }
+}
+
+impl<T> ShouldBeResponseBody for StreamingJson<T> {}
+impl<T: Serialize + JsonSchema + Send + 'static> ResponseBody for StreamingJson<T> {
+    fn body(gen: &mut SchemaGenerator) -> Vec<(StatusCode, Option<(Mime, Option<Schema>)>)> {
+        vec![(
+            StatusCode::OK,
+            Some((mime::APPLICATION_JSON, Some(gen.generate::<T>()))),
+        )]
+    }
+}
+
+/// [`Write`] sending everything written to it as chunks through a channel
+///
+/// It fails with [`io::ErrorKind::BrokenPipe`] once the receiving body has been dropped
+/// (i.e. when the client disconnected).
+struct ChannelWriter(mpsc::Sender<io::Result<Bytes>>);
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+//! Normalization of trailing slashes in request paths
+//!
+//! axum treats `/foo` and `/foo/` as different paths.
+//! Since clients are inconsistent about appending trailing slashes,
+//! [`normalize_trailing_slashes`] redirects or rewrites requests to the form a route has been registered with.
+//!
+//! Paths matching a route as they are are never touched,
+//! so routes which legitimately differ only in their trailing slash keep working.
+
+use std::sync::Arc;
+
+use axum::extract::Request;
+use axum::extract::State;
+use axum::http::uri::PathAndQuery;
+use axum::http::Uri;
+use axum::middleware;
+use axum::middleware::Next;
+use axum::response::IntoResponse;
+use axum::response::Redirect;
+use axum::response::Response;
+use axum::Router;
+use tower::Layer;
+
+use crate::GalvynRoute;
+
+/// How requests with a non-canonical trailing slash are handled
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TrailingSlash {
+    /// Respond with `308 Permanent Redirect` to the canonical path
+    Redirect,
+
+    /// Handle the request as if it had been made to the canonical path
+    Rewrite,
+}
+
+/// Wraps a router to normalize the trailing slashes of incoming requests
+///
+/// `routes` are the routes contained in `router` (see [`GalvynRouter::routes`](crate::GalvynRouter::routes)).
+/// Their paths are the canonical forms requests are normalized to.
+pub fn normalize_trailing_slashes(
+    router: Router,
+    routes: &[GalvynRoute],
+    mode: TrailingSlash,
+) -> Router {
+    let state = Arc::new(NormalizeState {
+        table: routes
+            .iter()
+            .map(|route| RoutePattern::new(&route.path))
+            .collect(),
+        mode,
+    });
+
+    // Routing happens before `Router::layer`'s middlewares,
+    // so the router is wrapped in a service and used as another router's fallback.
+    Router::new().fallback_service(middleware::from_fn_with_state(state, normalize).layer(router))
+}
+
+struct NormalizeState {
+    table: Vec<RoutePattern>,
+    mode: TrailingSlash,
+}
+
+impl NormalizeState {
+    fn matches(&self, path: &str) -> bool {
+        self.table.iter().any(|pattern| pattern.matches(path))
+    }
+}
+
+async fn normalize(
+    State(state): State<Arc<NormalizeState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if state.matches(path) || path == "/" {
+        return next.run(request).await;
+    }
+
+    let canonical = match path.strip_suffix('/') {
+        Some(stripped) => stripped.to_string(),
+        None => format!("{path}/"),
+    };
+    if !state.matches(&canonical) {
+        return next.run(request).await;
+    }
+
+    let path_and_query = match request.uri().query() {
+        Some(query) => format!("{canonical}?{query}"),
+        None => canonical,
+    };
+    match state.mode {
+        TrailingSlash::Redirect => Redirect::permanent(&path_and_query).into_response(),
+        TrailingSlash::Rewrite => {
+            let mut parts = request.uri().clone().into_parts();
+            parts.path_and_query = PathAndQuery::try_from(path_and_query).ok();
+            if let Ok(uri) = Uri::from_parts(parts) {
+                *request.uri_mut() = uri;
+            }
+            next.run(request).await
+        }
+    }
+}
+
+/// A route's path split into its segments
+struct RoutePattern(Vec<Segment>);
+
+enum Segment {
+    /// A literal segment
+    Static(String),
+
+    /// A `{param}` matching any single non-empty segment
+    Param,
+
+    /// A `{*param}` matching the remaining path
+    CatchAll,
+}
+
+impl RoutePattern {
+    fn new(path: &str) -> Self {
+        Self(
+            path.split('/')
+                .map(|segment| {
+                    if segment.starts_with("{*") && segment.ends_with('}') {
+                        Segment::CatchAll
+                    } else if segment.starts_with('{') && segment.ends_with('}') {
+                        Segment::Param
+                    } else {
+                        Segment::Static(segment.to_string())
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let mut segments = path.split('/');
+        for pattern in &self.0 {
+            match pattern {
+                Segment::CatchAll => return true,
+                Segment::Param => match segments.next() {
+                    Some(segment) if !segment.is_empty() => {}
+                    _ => return false,
+                },
+                Segment::Static(expected) => match segments.next() {
+                    Some(segment) if segment == expected => {}
+                    _ => return false,
+                },
+            }
+        }
+        segments.next().is_none()
+    }
+}
@@ -0,0 +1,51 @@
+//! Request bodies which validate themselves after being deserialized
+
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::de::Error;
+use serde::Deserialize;
+use serde::Deserializer;
+
+use crate::stuff::api_error::DynError;
+
+/// A newtype around a deserializable type which validates its content
+///
+/// This trait is normally derived together with the newtype's `Deserialize` and `JsonSchema` implementations:
+///
+/// ```rust,ignore
+/// #[derive(TryFromRequestBody)]
+/// #[try_from_request_body(validate = validate_email)]
+/// pub struct Email(String);
+///
+/// fn validate_email(email: &String) -> Result<(), &'static str> {
+///     if email.contains('@') {
+///         Ok(())
+///     } else {
+///         Err("Invalid email address")
+///     }
+/// }
+/// ```
+///
+/// The derived `Deserialize` runs the validation after deserializing the inner type
+/// and the derived `JsonSchema` reuses the inner type's schema.
+/// Therefore, the newtype can be used in any body like `Json<Email>`
+/// and a failed validation is rejected like any other malformed body.
+pub trait TryFromRequestBody: Sized {
+    /// The type to deserialize before validating
+    type Inner: DeserializeOwned + JsonSchema;
+
+    /// Validates the deserialized `inner` value and wraps it
+    fn try_from_request_body(inner: Self::Inner) -> Result<Self, DynError>;
+}
+
+/// Deserializes a [`TryFromRequestBody`]
+///
+/// This function implements the `Deserialize` generated by `#[derive(TryFromRequestBody)]`.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: TryFromRequestBody,
+    D: Deserializer<'de>,
+{
+    let inner = T::Inner::deserialize(deserializer)?;
+    T::try_from_request_body(inner).map_err(|error| D::Error::custom(error.to_string()))
+}
@@ -59,6 +59,83 @@ pub fn handler(
             Delimiter::Bracket,
             TokenStream::new(),
         )));
+    let accept = keyword
+        .remove(&Ident::new("accept", Span::call_site()))
+        .unwrap_or(TokenTree::Group(Group::new(
+            Delimiter::Bracket,
+            TokenStream::new(),
+        )));
+    let experimental = match keyword.remove(&Ident::new("experimental", Span::call_site())) {
+        None => Ident::new("false", Span::call_site()),
+        Some(TokenTree::Ident(value)) if value == "true" || value == "false" => value,
+        Some(value) => {
+            let err = quote_spanned! {value.span()=>
+                compile_error!("Expected `true` or `false`");
+            };
+            return quote! {
+                #err
+                #tokens
+            };
+        }
+    };
+    let (request_example, response_example) =
+        match keyword.remove(&Ident::new("example", Span::call_site())) {
+            None => (None, None),
+            Some(TokenTree::Group(group)) if group.delimiter() == Delimiter::Bracket => {
+                let parse::Args {
+                    positional,
+                    mut keyword,
+                } = match parse::parse_args(group.stream()) {
+                    Ok(x) => x,
+                    Err(err) => {
+                        return quote! {
+                            #err
+                            #tokens
+                        }
+                    }
+                };
+                let request = keyword.remove(&Ident::new("request", Span::call_site()));
+                let response = keyword.remove(&Ident::new("response", Span::call_site()));
+                if let Some(unexpected) = positional
+                    .into_iter()
+                    .next()
+                    .or_else(|| keyword.into_keys().next().map(TokenTree::Ident))
+                {
+                    let err = quote_spanned! {unexpected.span()=>
+                        compile_error!("Expected `request = ...` or `response = ...`");
+                    };
+                    return quote! {
+                        #err
+                        #tokens
+                    };
+                }
+                (request, response)
+            }
+            Some(value) => {
+                let err = quote_spanned! {value.span()=>
+                    compile_error!("Expected `example(request = ..., response = ...)`");
+                };
+                return quote! {
+                    #err
+                    #tokens
+                };
+            }
+        };
+    let operation_id = match keyword.remove(&Ident::new("operation_id", Span::call_site())) {
+        None => None,
+        Some(TokenTree::Literal(literal)) if literal.to_string().starts_with('"') => Some(literal),
+        Some(value) => {
+            let err = quote_spanned! {value.span()=>
+                compile_error!("Expected string literal");
+            };
+            return quote! {
+                #err
+                #tokens
+            };
+        }
+    };
+    let request_schema = keyword.remove(&Ident::new("request_schema", Span::call_site()));
+    let response_schema = keyword.remove(&Ident::new("response_schema", Span::call_site()));
     let core_crate = match keyword.remove(&Ident::new("core_crate", Span::call_site())) {
         None => quote! { ::galvyn::core },
         Some(value) => {
@@ -126,7 +203,12 @@ pub fn handler(
         })
         .collect::<Vec<_>>();
 
-    let request_parts = request_types.iter().map(|part| {
+    // A custom schema replaces the last argument's metadata, so it doesn't have to implement `RequestBody`
+    let request_part_types = match request_schema {
+        None => request_types.as_slice(),
+        Some(_) => request_types.split_last().map_or(&[][..], |(_, rest)| rest),
+    };
+    let request_parts = request_part_types.iter().map(|part| {
         quote_spanned! {part.span()=>
             #core_crate::get_metadata!(
                 #core_crate::handler::request_part::RequestPartMetadata,
@@ -135,7 +217,16 @@ pub fn handler(
         }
     });
 
-    let request_body = if let Some(body) = request_types.last() {
+    let request_body = if let Some(schema) = &request_schema {
+        quote_spanned! {schema.span()=>
+            Some(#core_crate::handler::request_body::RequestBodyMetadata {
+                body: |gen| (
+                    #core_crate::re_exports::mime::APPLICATION_JSON,
+                    Some(#schema(gen)),
+                ),
+            })
+        }
+    } else if let Some(body) = request_types.last() {
         quote_spanned! {body.span()=>
             #core_crate::get_metadata!(
                 #core_crate::handler::request_body::RequestBodyMetadata,
@@ -165,7 +256,14 @@ pub fn handler(
         quote! { None }
     };
 
-    let response_parts = response_types.iter().map(|part| {
+    // A custom schema replaces the last return type's metadata, so it doesn't have to implement `ResponseBody`
+    let response_part_types = match response_schema {
+        None => response_types.as_slice(),
+        Some(_) => response_types
+            .split_last()
+            .map_or(&[][..], |(_, rest)| rest),
+    };
+    let response_parts = response_part_types.iter().map(|part| {
         quote_spanned! {part.span()=>
             #core_crate::get_metadata!(
                 #core_crate::handler::response_part::ResponsePartMetadata,
@@ -174,7 +272,20 @@ pub fn handler(
         }
     });
 
-    let response_body = if let Some(body) = response_types.last() {
+    let response_body = if let Some(schema) = &response_schema {
+        quote_spanned! {schema.span()=>
+            Some(#core_crate::handler::response_body::ResponseBodyMetadata {
+                header: |_| Vec::new(),
+                body: |gen| vec![(
+                    #core_crate::re_exports::axum::http::StatusCode::OK,
+                    Some((
+                        #core_crate::re_exports::mime::APPLICATION_JSON,
+                        Some(#schema(gen)),
+                    )),
+                )],
+            })
+        }
+    } else if let Some(body) = response_types.last() {
         quote_spanned! {body.span()=>
             #core_crate::get_metadata!(
                 #core_crate::handler::response_body::ResponseBodyMetadata,
@@ -185,6 +296,18 @@ pub fn handler(
         quote! { None }
     };
 
+    let example = |value: Option<TokenTree>| match value {
+        None => quote! { None },
+        Some(value) => quote_spanned! {value.span()=>
+            Some(|| {
+                #core_crate::re_exports::serde_json::to_value(&#value)
+                    .expect("The handler's example should be serializable")
+            })
+        },
+    };
+    let request_example = example(request_example);
+    let response_example = example(response_example);
+
     let deprecated = attrs.iter().any(|attr| {
         attr.meta
             .path()
@@ -212,6 +335,11 @@ pub fn handler(
         _ => None,
     });
 
+    let operation_id = match operation_id {
+        None => quote! { stringify!(#func_ident) },
+        Some(literal) => quote! { #literal },
+    };
+
     let (impl_generics, type_generics, where_clause) = sig.generics.split_for_impl();
     let turbo_fish = type_generics.as_turbofish();
     let type_params = sig.generics.type_params().map(|param| &param.ident);
@@ -238,11 +366,14 @@ pub fn handler(
                     method: #core_crate::re_exports::axum::http::method::Method::#method,
                     path: #path,
                     deprecated: #deprecated,
+                    experimental: #experimental,
                     doc: &[#(
                         #doc,
                     )*],
                     ident: stringify!(#func_ident),
+                    operation_id: #operation_id,
                     tags: &#tags,
+                    accept: &#accept,
                     request_parts: {
                         let mut x = ::std::vec::Vec::new();
                         #(
@@ -260,6 +391,8 @@ pub fn handler(
                         x
                     },
                     response_body: #response_body,
+                    request_example: #request_example,
+                    response_example: #response_example,
                 }
             }
             fn method_router(&self) -> #core_crate::re_exports::axum::routing::MethodRouter {
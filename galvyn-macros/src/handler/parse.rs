@@ -22,8 +22,8 @@ pub struct Args {
     pub positional: Vec<TokenTree>,
     pub keyword: HashMap<Ident, TokenTree>,
 }
-fn parse_args(args: TokenStream) -> Result<Args, TokenStream> {
-    let mut args_iter = args.clone().into_iter();
+pub fn parse_args(args: TokenStream) -> Result<Args, TokenStream> {
+    let mut args_iter = args.clone().into_iter().peekable();
     enum Arg {
         Pos(TokenTree),
         Key(Ident, TokenTree),
@@ -57,7 +57,23 @@ fn parse_args(args: TokenStream) -> Result<Args, TokenStream> {
                     });
                 };
 
-                args_vec.push(Arg::Key(first, second));
+                // Values spanning multiple tokens (like paths) are collected into an invisible group
+                let mut rest = Vec::new();
+                while let Some(token) = args_iter.next_if(
+                    |token| !matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','),
+                ) {
+                    rest.push(token);
+                }
+                let value = if rest.is_empty() {
+                    second
+                } else {
+                    TokenTree::Group(Group::new(
+                        Delimiter::None,
+                        std::iter::once(second).chain(rest).collect(),
+                    ))
+                };
+
+                args_vec.push(Arg::Key(first, value));
             }
             TokenTree::Group(group) if matches!(group.delimiter(), Delimiter::Parenthesis) => {
                 let TokenTree::Ident(first) = first else {
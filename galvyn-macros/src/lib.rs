@@ -1,4 +1,5 @@
 mod handler;
+mod try_from_request_body;
 
 use proc_macro::TokenStream;
 
@@ -47,3 +48,8 @@ pub fn patch(args: TokenStream, input: TokenStream) -> TokenStream {
 pub fn trace(args: TokenStream, input: TokenStream) -> TokenStream {
     handler::handler(args.into(), input.into(), Some("TRACE")).into()
 }
+
+#[proc_macro_derive(TryFromRequestBody, attributes(try_from_request_body))]
+pub fn try_from_request_body(input: TokenStream) -> TokenStream {
+    try_from_request_body::try_from_request_body(input.into()).into()
+}
@@ -0,0 +1,124 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use quote::ToTokens;
+use syn::parse2;
+use syn::spanned::Spanned;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Fields;
+use syn::LitStr;
+use syn::Path;
+
+pub fn try_from_request_body(input: TokenStream) -> TokenStream {
+    match derive(input) {
+        Ok(tokens) => tokens,
+        Err(err) => err.into_compile_error(),
+    }
+}
+
+fn derive(input: TokenStream) -> syn::Result<TokenStream> {
+    let DeriveInput {
+        attrs,
+        ident,
+        generics,
+        data,
+        ..
+    } = parse2(input)?;
+
+    if !generics.params.is_empty() {
+        return Err(syn::Error::new(
+            generics.span(),
+            "TryFromRequestBody can't be derived for generic types",
+        ));
+    }
+
+    let mut validate = None;
+    let mut core_crate = quote! { ::galvyn::core };
+    for attr in &attrs {
+        if !attr.path().is_ident("try_from_request_body") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("validate") {
+                validate = Some(meta.value()?.parse::<Path>()?);
+                Ok(())
+            } else if meta.path.is_ident("core_crate") {
+                let literal = meta.value()?.parse::<LitStr>()?;
+                core_crate = literal.parse::<Path>()?.into_token_stream();
+                Ok(())
+            } else {
+                Err(meta.error("Unknown key"))
+            }
+        })?;
+    }
+    let Some(validate) = validate else {
+        return Err(syn::Error::new(
+            ident.span(),
+            "Missing `#[try_from_request_body(validate = ...)]`",
+        ));
+    };
+
+    let Data::Struct(data) = data else {
+        return Err(syn::Error::new(
+            ident.span(),
+            "TryFromRequestBody can only be derived for structs",
+        ));
+    };
+    let mut fields = data.fields.iter();
+    let (Some(field), None) = (fields.next(), fields.next()) else {
+        return Err(syn::Error::new(
+            data.fields.span(),
+            "TryFromRequestBody can only be derived for structs with exactly one field",
+        ));
+    };
+    let inner = &field.ty;
+    let construct = match &data.fields {
+        Fields::Named(_) => {
+            let field_ident = &field.ident;
+            quote! { Self { #field_ident: inner } }
+        }
+        _ => quote! { Self(inner) },
+    };
+
+    Ok(quote! {
+        impl #core_crate::stuff::try_from_request_body::TryFromRequestBody for #ident {
+            type Inner = #inner;
+
+            fn try_from_request_body(
+                inner: Self::Inner,
+            ) -> ::std::result::Result<Self, #core_crate::stuff::api_error::DynError> {
+                #validate(&inner)?;
+                Ok(#construct)
+            }
+        }
+
+        impl<'de> #core_crate::re_exports::serde::Deserialize<'de> for #ident {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+            where
+                D: #core_crate::re_exports::serde::Deserializer<'de>,
+            {
+                #core_crate::stuff::try_from_request_body::deserialize(deserializer)
+            }
+        }
+
+        impl #core_crate::re_exports::schemars::JsonSchema for #ident {
+            fn is_referenceable() -> bool {
+                <#inner as #core_crate::re_exports::schemars::JsonSchema>::is_referenceable()
+            }
+
+            fn schema_name() -> ::std::string::String {
+                <#inner as #core_crate::re_exports::schemars::JsonSchema>::schema_name()
+            }
+
+            fn schema_id() -> ::std::borrow::Cow<'static, str> {
+                <#inner as #core_crate::re_exports::schemars::JsonSchema>::schema_id()
+            }
+
+            fn json_schema(
+                gen: &mut #core_crate::re_exports::schemars::gen::SchemaGenerator,
+            ) -> #core_crate::re_exports::schemars::schema::Schema {
+                <#inner as #core_crate::re_exports::schemars::JsonSchema>::json_schema(gen)
+            }
+        }
+    })
+}
@@ -1,27 +1,110 @@
+use std::future;
 use std::mem;
 use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::PoisonError;
+use std::sync::RwLock;
 
 use axum::Router;
 use galvyn_core::re_exports::rorm::Database;
 use galvyn_core::registry::builder::RegistryBuilder;
-use galvyn_core::session;
+use galvyn_core::session::SessionSetup;
+use galvyn_core::stuff::trailing_slash::normalize_trailing_slashes;
+use galvyn_core::stuff::trailing_slash::TrailingSlash;
 use galvyn_core::GalvynRouter;
 use tokio::net::TcpListener;
+use tokio::signal;
+use tokio::sync::watch;
 use tracing::info;
-use tracing::Level;
-use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::EnvFilter;
+use tracing::warn;
 
 use crate::core::Module;
 use crate::error::GalvynError;
+use crate::setup::GalvynSetup;
 
 #[non_exhaustive]
 pub struct Galvyn;
 
+/// The shutdown handle of the most recently started webserver
+///
+/// Every [`RouterBuilder`] creates its own handle which [`RouterBuilder::start`] installs here,
+/// so a webserver started after a previous one has shut down doesn't stop immediately.
+static SHUTDOWN: LazyLock<RwLock<ShutdownHandle>> =
+    LazyLock::new(|| RwLock::new(ShutdownHandle::new()));
+
 impl Galvyn {
     pub fn new() -> ModuleBuilder {
-        ModuleBuilder::new()
+        Self::with_setup(GalvynSetup::default())
+    }
+
+    /// Like [`Galvyn::new`] but allows customizing the framework's global state (like tracing)
+    pub fn with_setup(setup: GalvynSetup) -> ModuleBuilder {
+        ModuleBuilder::new(setup)
+    }
+
+    /// Begins the graceful shutdown of the running webserver
+    ///
+    /// The server stops accepting new connections and waits for open requests to finish.
+    /// This is also triggered when the process receives `SIGINT` (ctrl-c) or `SIGTERM`.
+    pub fn shutdown() {
+        Self::shutdown_handle().shutdown();
+    }
+
+    /// Checks whether the graceful shutdown of the running webserver has begun
+    ///
+    /// Long-running handlers (like long-polling or streaming ones) should use this
+    /// (or [`Galvyn::shutdown_signal`]) to wind down their work.
+    pub fn is_shutting_down() -> bool {
+        Self::shutdown_handle().is_shutting_down()
+    }
+
+    /// Waits until the graceful shutdown of the running webserver has begun
+    pub async fn shutdown_signal() {
+        Self::shutdown_handle().signal().await
+    }
+
+    /// The shutdown handle of the running (or most recently started) webserver
+    pub fn shutdown_handle() -> ShutdownHandle {
+        SHUTDOWN
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Controls the graceful shutdown of a single webserver
+///
+/// Use [`RouterBuilder::shutdown_handle`] to obtain the handle of a server before starting it
+/// or [`Galvyn::shutdown_handle`] for the running one.
+#[derive(Clone, Debug)]
+pub struct ShutdownHandle(Arc<watch::Sender<bool>>);
+
+impl ShutdownHandle {
+    fn new() -> Self {
+        Self(Arc::new(watch::Sender::new(false)))
+    }
+
+    /// Begins the graceful shutdown
+    pub fn shutdown(&self) {
+        self.0.send_replace(true);
+    }
+
+    /// Checks whether the graceful shutdown has begun
+    pub fn is_shutting_down(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Waits until the graceful shutdown has begun
+    pub async fn signal(&self) {
+        let mut receiver = self.0.subscribe();
+        // The sender is owned by `self` and can't be dropped while waiting
+        let _ = receiver.wait_for(|shutting_down| *shutting_down).await;
+    }
+
+    /// Makes this the handle used by [`Galvyn::shutdown`] and its siblings
+    fn install(&self) {
+        *SHUTDOWN.write().unwrap_or_else(PoisonError::into_inner) = self.clone();
     }
 }
 
@@ -31,12 +114,8 @@ pub struct ModuleBuilder {
 }
 
 impl ModuleBuilder {
-    fn new() -> ModuleBuilder {
-        let registry = tracing_subscriber::registry()
-            .with(EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new(Level::INFO.as_str())))
-            .with(tracing_subscriber::fmt::layer());
-
-        registry.init();
+    fn new(setup: GalvynSetup) -> ModuleBuilder {
+        setup.tracing.init();
 
         let mut this = ModuleBuilder::default();
         this.register_module::<Database>();
@@ -51,17 +130,38 @@ impl ModuleBuilder {
 
     pub async fn init_modules(&mut self) -> Result<RouterBuilder, GalvynError> {
         self.modules.init().await?;
-        Ok(RouterBuilder {
-            routes: GalvynRouter::new(),
-        })
+        Ok(RouterBuilder::new())
+    }
+
+    /// Like [`ModuleBuilder::init_modules`] but the modules are only available on the current thread
+    ///
+    /// This allows every test in a binary to start its own application with fresh modules.
+    /// See [`RegistryBuilder::init_for_current_thread`] for its restrictions.
+    pub async fn init_modules_for_current_thread(&mut self) -> Result<RouterBuilder, GalvynError> {
+        self.modules.init_for_current_thread().await?;
+        Ok(RouterBuilder::new())
     }
 }
 
 pub struct RouterBuilder {
     routes: GalvynRouter,
+    session_setup: SessionSetup,
+    trailing_slash: Option<TrailingSlash>,
+    on_ready: Option<Box<dyn FnOnce(SocketAddr) + Send>>,
+    shutdown: ShutdownHandle,
 }
 
 impl RouterBuilder {
+    fn new() -> Self {
+        RouterBuilder {
+            routes: GalvynRouter::new(),
+            session_setup: SessionSetup::default(),
+            trailing_slash: None,
+            on_ready: None,
+            shutdown: ShutdownHandle::new(),
+        }
+    }
+
     /// Adds a router to the builder
     pub fn add_routes(&mut self, router: GalvynRouter) -> &mut Self {
         let this = mem::take(&mut self.routes);
@@ -69,15 +169,129 @@ impl RouterBuilder {
         self
     }
 
+    /// Configures the session cookie
+    ///
+    /// See [`SessionSetup::cross_site`] for frontends served from another site.
+    pub fn session_setup(&mut self, setup: SessionSetup) -> &mut Self {
+        self.session_setup = setup;
+        self
+    }
+
+    /// Normalizes trailing slashes in requests to the form the routes have been registered with
+    ///
+    /// See [`TrailingSlash`] for the available modes.
+    pub fn trailing_slash(&mut self, mode: TrailingSlash) -> &mut Self {
+        self.trailing_slash = Some(mode);
+        self
+    }
+
+    /// Registers a callback which is invoked once the webserver is listening
+    ///
+    /// It receives the address the server is bound to,
+    /// which is useful when binding to port `0` (for example in tests).
+    pub fn on_ready(&mut self, callback: impl FnOnce(SocketAddr) + Send + 'static) -> &mut Self {
+        self.on_ready = Some(Box::new(callback));
+        self
+    }
+
+    /// The handle to shut down the webserver once it has been started
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
     /// Starts the webserver
     pub async fn start(&mut self, socket_addr: SocketAddr) -> Result<(), GalvynError> {
-        let router = Router::from(mem::take(&mut self.routes)).layer(session::layer());
+        for issue in self.routes.validate_routes() {
+            warn!("Invalid route: {issue}");
+        }
+        for route in self.routes.routes() {
+            if route.experimental {
+                warn!(
+                    "Experimental route: {} {} ({}) is not production-ready",
+                    route.method, route.path, route.ident
+                );
+            }
+        }
+
+        let routes = mem::take(&mut self.routes);
+        let route_table = routes.routes().to_vec();
+        let router = self.session_setup.apply(Router::from(routes));
+        let router = match self.trailing_slash {
+            Some(mode) => normalize_trailing_slashes(router, &route_table, mode),
+            None => router,
+        };
+
+        let shutdown = self.shutdown.clone();
+        shutdown.install();
 
         let socket = TcpListener::bind(socket_addr).await?;
+        let socket_addr = socket.local_addr()?;
 
         info!("Starting to serve webserver on http://{socket_addr}");
-        axum::serve(socket, router).await?;
+        if let Some(on_ready) = self.on_ready.take() {
+            on_ready(socket_addr);
+        }
+        axum::serve(
+            socket,
+            router.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .with_graceful_shutdown(async move {
+            tokio::select! {
+                _ = shutdown.signal() => {}
+                _ = termination_signal() => {
+                    info!("Received termination signal");
+                    shutdown.shutdown();
+                }
+            }
+            info!("Shutting down webserver");
+        })
+        .await?;
 
         Ok(())
     }
 }
+
+/// Waits for the process to receive `SIGINT` (ctrl-c) or `SIGTERM`
+async fn termination_signal() {
+    let ctrl_c = async {
+        if signal::ctrl_c().await.is_err() {
+            // Without a signal handler, only `Galvyn::shutdown` can stop the server
+            future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_new_server_is_not_stopped_by_a_previous_shutdown() {
+        let previous = ShutdownHandle::new();
+        previous.install();
+        Galvyn::shutdown();
+        assert!(previous.is_shutting_down());
+
+        let next = ShutdownHandle::new();
+        next.install();
+        assert!(!Galvyn::is_shutting_down());
+        assert!(previous.is_shutting_down());
+    }
+}
@@ -13,6 +13,7 @@ pub use crate::galvyn::*;
 pub mod error;
 mod galvyn;
 mod macro_docs;
+pub mod setup;
 
 pub use macro_docs::*;
 pub use swaggapi;
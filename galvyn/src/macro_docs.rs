@@ -47,6 +47,51 @@ pub use galvyn_macros::get;
 ///     - optional
 ///     - list of string literal, for example `tags("foo", "bar)`
 ///
+/// - `accept`: A list of content types the request body may have
+///
+///     Requests with a body of any other content type are rejected with `415 Unsupported Media Type`.
+///     - optional
+///     - list of string literal, for example `accept("application/json")`
+///
+/// - `operation_id`: The OpenAPI operation's id
+///
+///     Generated clients usually name their methods after it.
+///     - optional
+///     - a string literal (defaults to the function's name), for example `operation_id = "listUsers"`
+///
+/// - `experimental`: Marks the handler as not production-ready yet
+///
+///     Experimental handlers are logged as a warning when the server starts.
+///     - optional
+///     - `true` or `false` (the default), for example `experimental = true`
+///
+/// - `request_schema`: A function providing the request body's schema
+///
+///     This replaces the schema generated from the last argument.
+///
+///     The argument doesn't have to implement `JsonSchema` then.
+///
+///     The body is documented as `application/json`.
+///     - optional
+///     - path to a `fn(&mut SchemaGenerator) -> Schema`, for example `request_schema = schemas::any`
+///
+/// - `response_schema`: A function providing the response body's schema
+///
+///     This replaces the schema generated from the return type.
+///
+///     The return type doesn't have to implement `JsonSchema` then.
+///
+///     The body is documented as `application/json` with status `200`.
+///     - optional
+///     - path to a `fn(&mut SchemaGenerator) -> Schema`, for example `response_schema = schemas::any`
+///
+/// - `example`: Example request and response bodies shown in the documentation
+///
+///     The values are serialized to json when the documentation is generated.
+///     - optional
+///     - `request` and / or `response` set to an expression implementing `Serialize`,
+///       for example `example(response = USER_EXAMPLE)`
+///
 /// ## Positional arguments
 /// Since `method` and `path` are required, they can alternatively be passed as positional arguments:
 /// - `#[handler(Get, "/")]`
@@ -89,6 +134,35 @@ pub use galvyn_macros::post;
 /// Unlike `#[handler]` it uses the http method `PUT`,
 /// for everything else please refer to [``#[handler]``](handler)
 pub use galvyn_macros::put;
+/// Derives [`TryFromRequestBody`](crate::core::stuff::try_from_request_body::TryFromRequestBody)
+/// for a newtype which validates its content after being deserialized
+///
+/// ```rust,ignore
+/// #[derive(TryFromRequestBody)]
+/// #[try_from_request_body(validate = validate_email)]
+/// pub struct Email(String);
+///
+/// fn validate_email(email: &String) -> Result<(), &'static str> {
+///     if email.contains('@') {
+///         Ok(())
+///     } else {
+///         Err("Invalid email address")
+///     }
+/// }
+/// ```
+///
+/// Besides `TryFromRequestBody` this derives `Deserialize` and `JsonSchema`,
+/// so don't derive those yourself.
+///
+/// ## Arguments
+/// - `validate`: A function taking a reference to the inner value and returning a `Result<(), E>`
+///     - **required**
+///     - `E` may be any error type convertible into a `Box<dyn Error>`, for example `&'static str`
+///
+/// - `core_crate`: The path to `galvyn_core`
+///     - optional
+///     - a string literal, for example `core_crate = "::galvyn_core"`
+pub use galvyn_macros::TryFromRequestBody;
 /// Turns a function into a documented api handler
 ///
 /// Unlike `#[handler]` it uses the http method `TRACE`,
@@ -0,0 +1,75 @@
+//! Configuration applied before any module is initialized
+
+use tracing::debug;
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::Registry;
+
+/// A type-erased tracing layer which can be composed by [`TracingSetup::Layers`]
+pub type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync + 'static>;
+
+/// Configuration passed to [`Galvyn::with_setup`](crate::Galvyn::with_setup)
+#[derive(Default)]
+pub struct GalvynSetup {
+    /// How the global tracing subscriber should be set up
+    pub tracing: TracingSetup,
+}
+
+impl GalvynSetup {
+    /// Sets how the global tracing subscriber should be set up
+    pub fn tracing(mut self, tracing: TracingSetup) -> Self {
+        self.tracing = tracing;
+        self
+    }
+}
+
+/// How the global tracing subscriber should be set up
+#[derive(Default)]
+pub enum TracingSetup {
+    /// Human-readable logs to stdout filtered by `RUST_LOG` (defaulting to `INFO`)
+    #[default]
+    Default,
+
+    /// Compose the given layers on top of a [`Registry`]
+    ///
+    /// No filter is added, so include one (for example an [`EnvFilter`]) in your layers.
+    ///
+    /// ```rust,ignore
+    /// TracingSetup::Layers(vec![
+    ///     EnvFilter::from_default_env().boxed(),
+    ///     tracing_subscriber::fmt::layer().json().boxed(),
+    /// ])
+    /// ```
+    Layers(Vec<BoxedLayer>),
+
+    /// Don't set up a subscriber
+    ///
+    /// Use this if your application installs its own subscriber.
+    Disabled,
+}
+
+impl TracingSetup {
+    /// Installs the global tracing subscriber
+    ///
+    /// If a global subscriber has already been installed (for example by another test in the same binary),
+    /// the existing one is kept.
+    pub(crate) fn init(self) {
+        let result = match self {
+            TracingSetup::Default => tracing_subscriber::registry()
+                .with(
+                    EnvFilter::try_from_default_env()
+                        .unwrap_or(EnvFilter::new(Level::INFO.as_str())),
+                )
+                .with(tracing_subscriber::fmt::layer())
+                .try_init(),
+            TracingSetup::Layers(layers) => tracing_subscriber::registry().with(layers).try_init(),
+            TracingSetup::Disabled => Ok(()),
+        };
+        if result.is_err() {
+            debug!("A global tracing subscriber has already been installed");
+        }
+    }
+}